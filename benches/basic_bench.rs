@@ -4,11 +4,9 @@ extern crate fastmap;
 extern crate rand;
 extern crate test;
 
-extern crate ordermap;
-
-use fastmap::FastMap;
+use fastmap::{murmur_hash64a, BuildMurmur2_64a, FastIndexMap, FastMap, FastMapConfig, RobinHoodMap, SplitMap, SwissMap};
 use std::collections::HashMap;
-use ordermap::OrderMap;
+use std::hash::BuildHasher;
 
 
 #[cfg(test)]
@@ -91,12 +89,12 @@ mod tests {
     }
 
 
-    // ********** Ordermap **********
+    // ********** Index map **********
 
     #[bench]
-    fn u64_insert_ordermap(b: &mut Bencher) {
+    fn u64_insert_index_map(b: &mut Bencher) {
         let data = get_random_range(VEC_COUNT);
-        let mut map = OrderMap::new();
+        let mut map = FastIndexMap::new();
 
         b.iter(|| {
             map.clear();
@@ -109,9 +107,9 @@ mod tests {
     }
 
     #[bench]
-    fn u64_get_ordermap(b: &mut Bencher) {
+    fn u64_get_index_map(b: &mut Bencher) {
         let data = get_random_range(VEC_COUNT);
-        let mut map: OrderMap<&u64, &u64>  = OrderMap::new();
+        let mut map: FastIndexMap<&u64, &u64>  = FastIndexMap::new();
 
         for s in data.iter() {
             test::black_box(map.insert(s, s)
@@ -128,9 +126,9 @@ mod tests {
     }
 
         #[bench]
-    fn string_insert_ordermap(b: &mut Bencher) {
+    fn string_insert_index_map(b: &mut Bencher) {
         let data = get_word_list();
-        let mut map = OrderMap::new();
+        let mut map = FastIndexMap::new();
 
         b.iter(|| {
             map.clear();
@@ -143,9 +141,9 @@ mod tests {
     }
 
     #[bench]
-    fn string_get_ordermap(b: &mut Bencher) {
+    fn string_get_index_map(b: &mut Bencher) {
         let data = get_word_list();
-        let mut map = OrderMap::new();
+        let mut map = FastIndexMap::new();
 
         for s in data.iter() {
             test::black_box(map.insert(s, s)
@@ -173,7 +171,7 @@ mod tests {
         b.iter(|| {
             map.clear();
             for s in data.iter() {
-                test::black_box({map.insert(*s, s)});
+                test::black_box(map.insert(*s, s));
             }
         });
     }
@@ -190,7 +188,7 @@ mod tests {
 
         b.iter(|| {
             for s in data.iter() {
-                test::black_box(map.contains_key(*s));
+                test::black_box(map.contains_key(s));
             }
         });
     }
@@ -204,7 +202,7 @@ mod tests {
         b.iter(|| {
             map.clear();
             for s in data.iter() {
-                test::black_box({map.insert(s, s)});
+                test::black_box(map.insert(s, s));
             }
         });
     }
@@ -226,6 +224,388 @@ mod tests {
         });
     }
 
+    #[bench]
+    fn u64_miss_fastmap(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map = FastMap::with_capacity(VEC_COUNT * 2);
+        for s in data.iter() {
+            map.insert(*s, s);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(&s.wrapping_add(1)));
+            }
+        });
+    }
+
+
+    // ********** Robin Hood **********
+
+    #[bench]
+    fn u64_insert_robin_hood(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map = RobinHoodMap::new();
+
+        b.iter(|| {
+            map.clear();
+            for s in data.iter() {
+                test::black_box(map.insert(*s, s));
+            }
+        });
+    }
+
+
+    #[bench]
+    fn u64_get_robin_hood(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map = RobinHoodMap::new();
+        for s in data.iter() {
+            map.insert(*s, s);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(s));
+            }
+        });
+    }
+
+    #[bench]
+    fn u64_miss_robin_hood(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map = RobinHoodMap::with_capacity(VEC_COUNT * 2);
+        for s in data.iter() {
+            map.insert(*s, s);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(&s.wrapping_add(1)));
+            }
+        });
+    }
+
+    #[bench]
+    fn string_insert_robin_hood(b: &mut Bencher) {
+        let data = get_word_list();
+
+        let mut map = RobinHoodMap::new();
+
+        b.iter(|| {
+            map.clear();
+            for s in data.iter() {
+                test::black_box(map.insert(s, s));
+            }
+        });
+    }
+
+
+    #[bench]
+    fn string_get_robin_hood(b: &mut Bencher) {
+        let data = get_word_list();
+
+        let mut map = RobinHoodMap::new();
+        for s in data.iter() {
+            map.insert(s, s);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(s));
+            }
+        });
+    }
+
+    // ********** Swiss **********
+
+    #[bench]
+    fn u64_insert_swiss(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map = SwissMap::new_swiss();
+
+        b.iter(|| {
+            map.clear();
+            for s in data.iter() {
+                test::black_box(map.insert(*s, s));
+            }
+        });
+    }
+
+
+    #[bench]
+    fn u64_get_swiss(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map = SwissMap::new_swiss();
+        for s in data.iter() {
+            map.insert(*s, s);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(s));
+            }
+        });
+    }
+
+    #[bench]
+    fn u64_miss_swiss(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map = SwissMap::with_capacity_and_hasher(VEC_COUNT * 2, BuildMurmur2_64a::new());
+        for s in data.iter() {
+            map.insert(*s, s);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(&s.wrapping_add(1)));
+            }
+        });
+    }
+
+    #[bench]
+    fn string_insert_swiss(b: &mut Bencher) {
+        let data = get_word_list();
+
+        let mut map = SwissMap::new_swiss();
+
+        b.iter(|| {
+            map.clear();
+            for s in data.iter() {
+                test::black_box(map.insert(s, s));
+            }
+        });
+    }
+
+
+    #[bench]
+    fn string_get_swiss(b: &mut Bencher) {
+        let data = get_word_list();
+
+        let mut map = SwissMap::new_swiss();
+        for s in data.iter() {
+            map.insert(s, s);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(s));
+            }
+        });
+    }
+
+    // ********** Large values **********
+
+    // Big enough that interleaved buckets spread a probe sequence over many cache lines.
+    type Large = [u64; 32];
+
+    #[bench]
+    fn large_insert_fastmap(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map: FastMap<u64, Large> = FastMap::new();
+
+        b.iter(|| {
+            map.clear();
+            for s in data.iter() {
+                test::black_box(map.insert(*s, [*s; 32]));
+            }
+        });
+    }
+
+
+    #[bench]
+    fn large_get_fastmap(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map: FastMap<u64, Large> = FastMap::new();
+        for s in data.iter() {
+            map.insert(*s, [*s; 32]);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(s));
+            }
+        });
+    }
+
+    #[bench]
+    fn large_miss_fastmap(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map: FastMap<u64, Large> = FastMap::with_capacity(VEC_COUNT * 2);
+        for s in data.iter() {
+            map.insert(*s, [*s; 32]);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(&s.wrapping_add(1)));
+            }
+        });
+    }
+
+    #[bench]
+    fn large_insert_split(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map: SplitMap<u64, Large> = FastMap::new_split();
+
+        b.iter(|| {
+            map.clear();
+            for s in data.iter() {
+                test::black_box(map.insert(*s, [*s; 32]));
+            }
+        });
+    }
+
+
+    #[bench]
+    fn large_get_split(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let mut map: SplitMap<u64, Large> = FastMap::new_split();
+        for s in data.iter() {
+            map.insert(*s, [*s; 32]);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(s));
+            }
+        });
+    }
+
+    #[bench]
+    fn large_miss_split(b: &mut Bencher) {
+        let data = get_random_range(VEC_COUNT);
+
+        let config = FastMapConfig::new().capacity(VEC_COUNT * 2);
+        let mut map: SplitMap<u64, Large> = FastMap::with_config_and_hasher(config, BuildMurmur2_64a::new());
+        for s in data.iter() {
+            map.insert(*s, [*s; 32]);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(&s.wrapping_add(1)));
+            }
+        });
+    }
+
+    // ********** String keys **********
+
+    // Murmur2_64a buffers up to 32 bytes and streams longer keys, hashing through the
+    // Hasher should stay close to one-shot murmur_hash64a over the same bytes.
+
+    #[bench]
+    fn short_string_hash_one_shot(b: &mut Bencher) {
+        bench_one_shot(b, &get_string_keys(VEC_COUNT, 16));
+    }
+
+    #[bench]
+    fn short_string_hash_hasher(b: &mut Bencher) {
+        bench_hasher(b, &get_string_keys(VEC_COUNT, 16));
+    }
+
+    #[bench]
+    fn long_string_hash_one_shot(b: &mut Bencher) {
+        bench_one_shot(b, &get_string_keys(VEC_COUNT, 64));
+    }
+
+    #[bench]
+    fn long_string_hash_hasher(b: &mut Bencher) {
+        bench_hasher(b, &get_string_keys(VEC_COUNT, 64));
+    }
+
+    #[bench]
+    fn long_string_insert_built_in(b: &mut Bencher) {
+        let data = get_string_keys(VEC_COUNT, 64);
+        let mut map = HashMap::new();
+
+        b.iter(|| {
+            map.clear();
+            for s in data.iter() {
+                test::black_box(map.insert(s, s));
+            }
+        });
+    }
+
+    #[bench]
+    fn long_string_insert_fastmap(b: &mut Bencher) {
+        let data = get_string_keys(VEC_COUNT, 64);
+        let mut map = FastMap::new();
+
+        b.iter(|| {
+            map.clear();
+            for s in data.iter() {
+                test::black_box(map.insert(s, s));
+            }
+        });
+    }
+
+    #[bench]
+    fn long_string_get_built_in(b: &mut Bencher) {
+        let data = get_string_keys(VEC_COUNT, 64);
+        let mut map = HashMap::new();
+        for s in data.iter() {
+            map.insert(s, s);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(s));
+            }
+        });
+    }
+
+    #[bench]
+    fn long_string_get_fastmap(b: &mut Bencher) {
+        let data = get_string_keys(VEC_COUNT, 64);
+        let mut map = FastMap::new();
+        for s in data.iter() {
+            map.insert(s, s);
+        }
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(map.contains_key(s));
+            }
+        });
+    }
+
+    fn bench_one_shot(b: &mut Bencher, data: &[String]) {
+        let mut bytes = Vec::new();
+
+        b.iter(|| {
+            for s in data.iter() {
+                // The same bytes `str` feeds a Hasher, its terminator included.
+                bytes.clear();
+                bytes.extend_from_slice(s.as_bytes());
+                bytes.push(0xff);
+                test::black_box(murmur_hash64a(&bytes, 0));
+            }
+        });
+    }
+
+    fn bench_hasher(b: &mut Bencher, data: &[String]) {
+        let build = BuildMurmur2_64a::new();
+
+        b.iter(|| {
+            for s in data.iter() {
+                test::black_box(build.hash_one(s));
+            }
+        });
+    }
+
     // ********** Misc **********
 
 
@@ -248,6 +628,11 @@ mod tests {
     }
 
 
+    fn get_string_keys(count: usize, len: usize) -> Vec<String> {
+        get_random_range(count).iter().map(|i| format!("{:0>1$}", i % 1_000_000_000_000, len)).collect()
+    }
+
+
     fn get_word_list() -> Vec<String> {
         use std::fs::File;
         use std::io::{BufRead, BufReader};
@@ -257,11 +642,8 @@ mod tests {
         let f = File::open("C:\\home\\jesper\\rust\\fastmap\\benches\\words.txt").expect("Failed to open words.txt");
         let file = BufReader::new(&f);
 
-        for line in file.lines() {
-            match line {
-                Ok(l) => vec.push(l),
-                Err(_) => {}
-            }
+        for l in file.lines().map_while(Result::ok) {
+            vec.push(l);
         }
 
         vec
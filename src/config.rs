@@ -0,0 +1,160 @@
+/// When a FastMap checks whether it has to grow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckPolicy {
+    /// Checks the load before every insert.
+    EveryInsert,
+    /// Checks the load once every `n` inserts, making room for all `n` at once.
+    ///
+    /// Takes the check off the hot path at the cost of growing up to `n` entries early.
+    /// An `n` past the table's grow threshold is clamped to it, so the map never grows
+    /// more than one step ahead.
+    Amortized(usize),
+}
+
+/// Tuning for how full a FastMap gets and how it grows.
+///
+/// # Examples
+///
+/// ```
+/// use fastmap::{CheckPolicy, FastMap, FastMapConfig};
+///
+/// let config = FastMapConfig::new()
+///     .capacity(1024)
+///     .max_load_factor(0.5)
+///     .growth_factor(4)
+///     .check_policy(CheckPolicy::Amortized(16));
+///
+/// let mut map: FastMap<u64, u64> = FastMap::with_config(config);
+/// for i in 0..1000 {
+///     map.insert(i, i);
+/// }
+/// assert!(map.load_rate() <= 50.0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FastMapConfig {
+    pub(crate) capacity: usize,
+    pub(crate) max_load_factor: f64,
+    pub(crate) min_load_factor: f64,
+    pub(crate) growth_shift: u32,
+    pub(crate) check_policy: CheckPolicy,
+    pub(crate) resize_step: usize,
+}
+
+impl FastMapConfig {
+    /// The defaults: 70% max load, doubling in one go, checked on every insert.
+    pub fn new() -> FastMapConfig {
+        FastMapConfig {
+            capacity: 4,
+            max_load_factor: 0.7,
+            min_load_factor: 0.0,
+            growth_shift: 1,
+            check_policy: CheckPolicy::EveryInsert,
+            resize_step: 0,
+        }
+    }
+
+    /// Sets the initial number of buckets, rounded up to a power of 2.
+    pub fn capacity(mut self, capacity: usize) -> FastMapConfig {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets the share of buckets, live entries and tombstones, that may be in use before
+    /// the table grows. Lower means shorter probes and more memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `factor` is above 0 and below 1.
+    pub fn max_load_factor(mut self, factor: f64) -> FastMapConfig {
+        assert!(factor > 0.0 && factor < 1.0, "Max load factor must be between 0 and 1, got {}", factor);
+        assert!(self.min_load_factor * 2.0 < factor, "Max load factor must be above twice the min load factor {}, got {}", self.min_load_factor, factor);
+        self.max_load_factor = factor;
+        self
+    }
+
+    /// Sets the share of buckets holding live entries below which a removal halves the
+    /// table, giving memory back after a burst. 0, the default, never shrinks.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `factor` is at least 0 and below half the max load factor, so the
+    /// halved table isn't grown straight back by the next insert.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::{FastMap, FastMapConfig};
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::with_config(FastMapConfig::new().min_load_factor(0.1));
+    ///
+    /// for i in 0..1000 {
+    ///     map.insert(i, i);
+    /// }
+    /// let peak = map.capacity();
+    ///
+    /// for i in 0..990 {
+    ///     map.remove(&i);
+    /// }
+    /// assert!(map.capacity() < peak);
+    /// ```
+    pub fn min_load_factor(mut self, factor: f64) -> FastMapConfig {
+        assert!(factor >= 0.0 && factor * 2.0 < self.max_load_factor, "Min load factor must be at least 0 and below half the max load factor {}, got {}", self.max_load_factor, factor);
+        self.min_load_factor = factor;
+        self
+    }
+
+    /// Sets how many times larger the table gets each time it grows.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `factor` is a power of 2 and at least 2, the table size always is.
+    pub fn growth_factor(mut self, factor: usize) -> FastMapConfig {
+        assert!(factor >= 2 && factor.is_power_of_two(), "Growth factor must be a power of 2 and at least 2, got {}", factor);
+        self.growth_shift = factor.trailing_zeros();
+        self
+    }
+
+    /// Sets when the map checks whether it has to grow.
+    ///
+    /// # Panics
+    ///
+    /// Panics on `CheckPolicy::Amortized(0)`.
+    pub fn check_policy(mut self, policy: CheckPolicy) -> FastMapConfig {
+        if let CheckPolicy::Amortized(n) = policy {
+            assert!(n > 0, "Amortized check interval must be at least 1");
+        }
+        self.check_policy = policy;
+        self
+    }
+
+    /// Grows the table incrementally instead of rehashing it all inside one insert.
+    ///
+    /// The old buckets are kept next to the grown table, and every insert or remove
+    /// moves at least `step` of them over. Lookups consult both until all are moved.
+    /// Pass 0, the default, to rehash in one go.
+    ///
+    /// Moving has to be done before the grown table fills up, which takes about
+    /// `max_load_factor * (growth_factor - 1)` inserts per old bucket. When `step` is too
+    /// small for that more buckets are moved per operation, around 2 at the default load
+    /// and growth factors, around 10 at a max load factor of 0.1.
+    pub fn incremental_resize(mut self, step: usize) -> FastMapConfig {
+        self.resize_step = step;
+        self
+    }
+
+
+    /// Load limit in entries for a table of `lim` buckets.
+    #[inline]
+    pub(crate) fn max_load(&self, lim: usize) -> usize {
+        let load = (lim as f64 * self.max_load_factor) as usize;
+
+        // Keep a free bucket beyond the load limit, probing relies on finding one.
+        load.min(lim - 1)
+    }
+}
+
+impl Default for FastMapConfig {
+    fn default() -> FastMapConfig {
+        FastMapConfig::new()
+    }
+}
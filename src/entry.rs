@@ -0,0 +1,263 @@
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+
+use {BuildMurmur2_64a, FastMap};
+use storage::{DefaultStorage, Storage};
+
+/// A view into a single entry in a FastMap, which may either be vacant or occupied.
+///
+/// Constructed from the `entry` method on `FastMap`.
+pub enum Entry<'a, K: 'a, V: 'a, S: 'a = BuildMurmur2_64a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    Occupied(OccupiedEntry<'a, K, V, S, T>),
+    Vacant(VacantEntry<'a, K, V, S, T>),
+}
+
+/// A view into an occupied entry in a FastMap.
+pub struct OccupiedEntry<'a, K: 'a, V: 'a, S: 'a = BuildMurmur2_64a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    map: &'a mut FastMap<K, V, S, T>,
+    ix: usize,
+}
+
+/// A view into a vacant entry in a FastMap.
+pub struct VacantEntry<'a, K: 'a, V: 'a, S: 'a = BuildMurmur2_64a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    map: &'a mut FastMap<K, V, S, T>,
+    hash: u64,
+    key: K,
+    ix: usize,
+}
+
+/// The error returned by `try_insert` when the key already exists.
+///
+/// Contains the occupied entry and the value that was not inserted.
+pub struct OccupiedError<'a, K: 'a, V: 'a, S: 'a = BuildMurmur2_64a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    /// The entry in the map that was already occupied.
+    pub entry: OccupiedEntry<'a, K, V, S, T>,
+    /// The value which was not inserted, because the entry was already occupied.
+    pub value: V,
+}
+
+
+// ***************** Entry *********************
+
+impl<'a, K, V, S, T> Entry<'a, K, V, S, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    /// Ensures a value is in the entry by inserting the default if empty,
+    /// and returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// *map.entry(21).or_insert(41) += 1;
+    /// assert_eq!(*map.get(&21).unwrap(), 42);
+    /// ```
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of `default` if empty,
+    /// and returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, String> = FastMap::new();
+    /// map.entry(21).or_insert_with(|| "Eat my shorts".to_string());
+    /// assert_eq!(map.get(&21).unwrap(), "Eat my shorts");
+    /// ```
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting `V::default()` if empty,
+    /// and returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, Vec<u64>> = FastMap::new();
+    /// map.entry(21).or_default().push(42);
+    /// assert_eq!(*map.get(&21).unwrap(), vec![42]);
+    /// ```
+    pub fn or_default(self) -> &'a mut V
+        where V: Default {
+        self.or_insert_with(V::default)
+    }
+
+    /// Runs `f` on the value if the entry is occupied, before any potential insert.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// map.entry(21).and_modify(|v| *v += 1).or_insert(42);
+    /// assert_eq!(*map.get(&21).unwrap(), 42);
+    /// map.entry(21).and_modify(|v| *v += 1).or_insert(42);
+    /// assert_eq!(*map.get(&21).unwrap(), 43);
+    /// ```
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref entry) => entry.key(),
+            Entry::Vacant(ref entry) => entry.key(),
+        }
+    }
+}
+
+
+// ***************** Occupied Entry *********************
+
+impl<'a, K, V, S, T> OccupiedEntry<'a, K, V, S, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    pub(crate) fn new(map: &'a mut FastMap<K, V, S, T>, ix: usize) -> Self {
+        OccupiedEntry { map, ix }
+    }
+
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        self.map.cache.key(self.ix)
+    }
+
+    /// Returns a reference to the value in the entry.
+    pub fn get(&self) -> &V {
+        self.map.cache.value(self.ix)
+    }
+
+    /// Returns a mutable reference to the value in the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map.cache.value_mut(self.ix)
+    }
+
+    /// Converts the entry into a mutable reference to its value, bound to the map's lifetime.
+    pub fn into_mut(self) -> &'a mut V {
+        self.map.cache.value_mut(self.ix)
+    }
+
+    /// Sets the value of the entry and returns the old value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::{Entry, FastMap};
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// map.insert(21, 42);
+    ///
+    /// if let Entry::Occupied(mut entry) = map.entry(21) {
+    ///     assert_eq!(entry.insert(43), 42);
+    /// }
+    /// assert_eq!(*map.get(&21).unwrap(), 43);
+    /// ```
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    /// Takes the value out of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::{Entry, FastMap};
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// map.insert(21, 42);
+    ///
+    /// if let Entry::Occupied(entry) = map.entry(21) {
+    ///     assert_eq!(entry.remove(), 42);
+    /// }
+    /// assert!(!map.contains_key(&21));
+    /// ```
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Takes the key and value out of the map.
+    pub fn remove_entry(self) -> (K, V) {
+        let entry = self.map.remove_at(self.ix);
+        self.map.ensure_min_load();
+        entry
+    }
+}
+
+
+// ***************** Vacant Entry *********************
+
+impl<'a, K, V, S, T> VacantEntry<'a, K, V, S, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    pub(crate) fn new(map: &'a mut FastMap<K, V, S, T>, hash: u64, key: K, ix: usize) -> Self {
+        VacantEntry { map, hash, key, ix }
+    }
+
+    /// Returns a reference to the key that would be used when inserting.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Takes ownership of the key.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the value into the map and returns a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let map = self.map;
+
+        map.insert_at(self.ix, self.hash, self.key, value);
+        map.cache.value_mut(self.ix)
+    }
+}
+
+
+// ***************** Occupied Error *********************
+
+impl<'a, K, V, S, T> fmt::Debug for OccupiedError<'a, K, V, S, T>
+    where K: Eq + Hash + fmt::Debug, V: fmt::Debug, T: Storage<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+
+impl<'a, K, V, S, T> fmt::Display for OccupiedError<'a, K, V, S, T>
+    where K: Eq + Hash + fmt::Debug, V: fmt::Debug, T: Storage<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value, self.entry.key(), self.entry.get())
+    }
+}
+
+impl<'a, K, V, S, T> Error for OccupiedError<'a, K, V, S, T>
+    where K: Eq + Hash + fmt::Debug, V: fmt::Debug, T: Storage<K, V> {}
@@ -0,0 +1,107 @@
+use std::alloc::{handle_alloc_error, Layout};
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// The error returned by `try_reserve` when the table could not be grown.
+///
+/// The map is left untouched when this is returned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The requested number of entries needs a table larger than can be addressed.
+    CapacityOverflow,
+    /// The allocator failed to provide memory for the table.
+    AllocError {
+        /// The layout of the table allocation that failed.
+        layout: Layout,
+    },
+}
+
+impl TryReserveError {
+    /// Fails the way an infallible growth path should, panicking on overflow
+    /// and handing allocation failures to the global handler.
+    pub(crate) fn bail(self) -> ! {
+        match self {
+            TryReserveError::CapacityOverflow => panic!("capacity overflow"),
+            TryReserveError::AllocError { layout } => handle_alloc_error(layout),
+        }
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TryReserveError::CapacityOverflow =>
+                write!(f, "memory allocation failed because the computed capacity exceeded the collection's maximum"),
+            TryReserveError::AllocError { layout } =>
+                write!(f, "memory allocation of {} bytes failed", layout.size()),
+        }
+    }
+}
+
+impl Error for TryReserveError {}
+
+/// The error returned when saving or loading a snapshot, see `FastMap::save_to`.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Reading or writing the snapshot failed, truncated input shows up here too.
+    Io(io::Error),
+    /// The input doesn't start like a FastMap snapshot.
+    BadMagic,
+    /// The snapshot was written in a format version this build can't read.
+    UnsupportedVersion(u32),
+    /// The snapshot was hashed by another hash function than the map being loaded uses.
+    HasherMismatch {
+        /// The hasher id of the map being loaded.
+        expected: u32,
+        /// The hasher id stored in the snapshot.
+        found: u32,
+    },
+    /// The snapshot holds keys or values of another size than the map being loaded.
+    LayoutMismatch,
+    /// The snapshot doesn't match its checksum.
+    ChecksumMismatch,
+    /// The snapshot holds a table no FastMap could have written.
+    Corrupt(&'static str),
+    /// There was no memory for the table the snapshot describes.
+    Reserve(TryReserveError),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SnapshotError::Io(ref err) => write!(f, "snapshot i/o failed: {}", err),
+            SnapshotError::BadMagic => write!(f, "not a FastMap snapshot"),
+            SnapshotError::UnsupportedVersion(version) =>
+                write!(f, "unsupported snapshot version {}", version),
+            SnapshotError::HasherMismatch { expected, found } =>
+                write!(f, "snapshot was hashed with hasher {}, the map uses hasher {}", found, expected),
+            SnapshotError::LayoutMismatch => write!(f, "snapshot key or value size doesn't match the map"),
+            SnapshotError::ChecksumMismatch => write!(f, "snapshot checksum mismatch"),
+            SnapshotError::Corrupt(what) => write!(f, "corrupt snapshot: {}", what),
+            SnapshotError::Reserve(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            SnapshotError::Io(ref err) => Some(err),
+            SnapshotError::Reserve(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> SnapshotError {
+        SnapshotError::Io(err)
+    }
+}
+
+impl From<TryReserveError> for SnapshotError {
+    fn from(err: TryReserveError) -> SnapshotError {
+        SnapshotError::Reserve(err)
+    }
+}
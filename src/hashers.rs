@@ -0,0 +1,551 @@
+//! Additional hash functions for FastMap.
+//!
+//! Every hasher comes with a `Build*` type implementing `BuildHasher`, so it can be handed
+//! to `FastMap::with_hasher` or used with `std::collections::HashMap`. All of them read
+//! bytes and integers little-endian and give the same hashes on every platform.
+//!
+//! * `Murmur3_x64_128` is MurmurHash3 x64_128, returning the low 64 bits of the digest.
+//! * `XxHash64` is xxHash64, fast on long keys such as strings and byte buffers.
+//! * `FibonacciHasher` is a multiply-shift hasher for integer keys, a single multiply per write.
+
+use std::hash::BuildHasher;
+use std::hash::Hasher;
+
+macro_rules! le_integer_writes {
+    () => {
+        #[inline]
+        fn write_u8(&mut self, i: u8) {
+            self.write(&[i]);
+        }
+
+        #[inline]
+        fn write_u16(&mut self, i: u16) {
+            self.write(&i.to_le_bytes());
+        }
+
+        #[inline]
+        fn write_u32(&mut self, i: u32) {
+            self.write(&i.to_le_bytes());
+        }
+
+        #[inline]
+        fn write_u64(&mut self, i: u64) {
+            self.write(&i.to_le_bytes());
+        }
+
+        #[inline]
+        fn write_usize(&mut self, i: usize) {
+            self.write_u64(i as u64);
+        }
+    };
+}
+
+#[inline]
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+#[inline]
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(bytes);
+    u32::from_le_bytes(buf)
+}
+
+
+// ***** Murmur3_x64_128 *****
+
+const MURMUR3_C1: u64 = 0x87c37b91114253d5;
+const MURMUR3_C2: u64 = 0x4cf5ad432745937f;
+
+/// MurmurHash3 x64_128 hasher, build one per key through `BuildMurmur3_x64_128`.
+///
+/// Full 16 byte blocks are mixed in as they are written, `finish` returns the low 64 bits
+/// of the digest and `finish128` the whole of it.
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug)]
+pub struct Murmur3_x64_128 {
+    h1: u64,
+    h2: u64,
+    len: u64,
+    tail: [u8; 16],
+    tail_len: usize,
+}
+
+impl Murmur3_x64_128 {
+    pub fn new() -> Murmur3_x64_128 {
+        Murmur3_x64_128::with_seed(0)
+    }
+
+    /// Creates a Murmur3_x64_128 with both halves of the state set to `seed`.
+    ///
+    /// Seeds below 2^32 give the same hashes as the reference implementation's `uint32_t` seed.
+    pub fn with_seed(seed: u64) -> Murmur3_x64_128 {
+        Murmur3_x64_128 {
+            h1: seed,
+            h2: seed,
+            len: 0,
+            tail: [0; 16],
+            tail_len: 0,
+        }
+    }
+
+    /// Returns the full 128 bit digest as `(h1, h2)`.
+    pub fn finish128(&self) -> (u64, u64) {
+        let mut h1 = self.h1;
+        let mut h2 = self.h2;
+        let tail = &self.tail[..self.tail_len];
+
+        if tail.len() > 8 {
+            h2 ^= murmur3_k2(read_u64(&tail[8..]));
+        }
+
+        if !tail.is_empty() {
+            h1 ^= murmur3_k1(read_u64(&tail[..tail.len().min(8)]));
+        }
+
+        h1 ^= self.len;
+        h2 ^= self.len;
+
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+
+        h1 = murmur3_fmix(h1);
+        h2 = murmur3_fmix(h2);
+
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+
+        (h1, h2)
+    }
+
+    #[inline]
+    fn block(&mut self, block: &[u8]) {
+        self.h1 ^= murmur3_k1(read_u64(&block[..8]));
+        self.h1 = self.h1.rotate_left(27).wrapping_add(self.h2);
+        self.h1 = self.h1.wrapping_mul(5).wrapping_add(0x52dce729);
+
+        self.h2 ^= murmur3_k2(read_u64(&block[8..]));
+        self.h2 = self.h2.rotate_left(31).wrapping_add(self.h1);
+        self.h2 = self.h2.wrapping_mul(5).wrapping_add(0x38495ab5);
+    }
+}
+
+impl Default for Murmur3_x64_128 {
+    fn default() -> Murmur3_x64_128 {
+        Murmur3_x64_128::new()
+    }
+}
+
+impl Hasher for Murmur3_x64_128 {
+    fn write(&mut self, mut msg: &[u8]) {
+        self.len += msg.len() as u64;
+
+        if self.tail_len > 0 {
+            let take = (16 - self.tail_len).min(msg.len());
+            self.tail[self.tail_len..self.tail_len + take].copy_from_slice(&msg[..take]);
+            self.tail_len += take;
+            msg = &msg[take..];
+
+            if self.tail_len < 16 {
+                return;
+            }
+
+            let block = self.tail;
+            self.block(&block);
+            self.tail_len = 0;
+        }
+
+        let blocks = msg.chunks_exact(16);
+        let rest = blocks.remainder();
+
+        for block in blocks {
+            self.block(block);
+        }
+
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+    }
+
+    le_integer_writes!();
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.finish128().0
+    }
+}
+
+#[inline]
+fn murmur3_k1(mut k1: u64) -> u64 {
+    k1 = k1.wrapping_mul(MURMUR3_C1);
+    k1 = k1.rotate_left(31);
+    k1.wrapping_mul(MURMUR3_C2)
+}
+
+#[inline]
+fn murmur3_k2(mut k2: u64) -> u64 {
+    k2 = k2.wrapping_mul(MURMUR3_C2);
+    k2 = k2.rotate_left(33);
+    k2.wrapping_mul(MURMUR3_C1)
+}
+
+#[inline]
+fn murmur3_fmix(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51afd7ed558ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ceb9fe1a85ec53);
+    k ^= k >> 33;
+    k
+}
+
+/// MurmurHash3 x64_128 of `key`, returned as `(h1, h2)`.
+pub fn murmur3_x64_128(key: &[u8], seed: u64) -> (u64, u64) {
+    let mut hasher = Murmur3_x64_128::with_seed(seed);
+    hasher.write(key);
+    hasher.finish128()
+}
+
+/// Builds fresh Murmur3_x64_128 hashers.
+///
+/// # Examples
+///
+/// ```
+/// use fastmap::FastMap;
+/// use fastmap::hashers::BuildMurmur3_x64_128;
+///
+/// let mut map: FastMap<String, u64, _> = FastMap::with_hasher(BuildMurmur3_x64_128::new());
+/// map.insert("Eat my shorts".to_string(), 42);
+/// assert_eq!(*map.get("Eat my shorts").unwrap(), 42);
+/// ```
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildMurmur3_x64_128 {
+    seed: u64
+}
+
+impl BuildMurmur3_x64_128 {
+    pub fn new() -> BuildMurmur3_x64_128 {
+        BuildMurmur3_x64_128::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> BuildMurmur3_x64_128 {
+        BuildMurmur3_x64_128 { seed }
+    }
+
+    /// The seed handed to every Murmur3_x64_128 this builds.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl BuildHasher for BuildMurmur3_x64_128 {
+    type Hasher = Murmur3_x64_128;
+    fn build_hasher(&self) -> Self::Hasher {
+        Murmur3_x64_128::with_seed(self.seed)
+    }
+}
+
+
+// ***** XxHash64 *****
+
+const XX_PRIME_1: u64 = 0x9E3779B185EBCA87;
+const XX_PRIME_2: u64 = 0xC2B2AE3D27D4EB4F;
+const XX_PRIME_3: u64 = 0x165667B19E3779F9;
+const XX_PRIME_4: u64 = 0x85EBCA77C2B2AE63;
+const XX_PRIME_5: u64 = 0x27D4EB2F165667C5;
+
+/// xxHash64 hasher, build one per key through `BuildXxHash64`.
+///
+/// Full 32 byte stripes are mixed in as they are written, the rest is held until `finish`.
+#[derive(Clone, Debug)]
+pub struct XxHash64 {
+    seed: u64,
+    len: u64,
+    acc: [u64; 4],
+    tail: [u8; 32],
+    tail_len: usize,
+}
+
+impl XxHash64 {
+    pub fn new() -> XxHash64 {
+        XxHash64::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> XxHash64 {
+        XxHash64 {
+            seed,
+            len: 0,
+            acc: [
+                seed.wrapping_add(XX_PRIME_1).wrapping_add(XX_PRIME_2),
+                seed.wrapping_add(XX_PRIME_2),
+                seed,
+                seed.wrapping_sub(XX_PRIME_1),
+            ],
+            tail: [0; 32],
+            tail_len: 0,
+        }
+    }
+
+    #[inline]
+    fn stripe(&mut self, stripe: &[u8]) {
+        for (acc, lane) in self.acc.iter_mut().zip(stripe.chunks_exact(8)) {
+            *acc = xx_round(*acc, read_u64(lane));
+        }
+    }
+}
+
+impl Default for XxHash64 {
+    fn default() -> XxHash64 {
+        XxHash64::new()
+    }
+}
+
+impl Hasher for XxHash64 {
+    fn write(&mut self, mut msg: &[u8]) {
+        self.len += msg.len() as u64;
+
+        if self.tail_len > 0 {
+            let take = (32 - self.tail_len).min(msg.len());
+            self.tail[self.tail_len..self.tail_len + take].copy_from_slice(&msg[..take]);
+            self.tail_len += take;
+            msg = &msg[take..];
+
+            if self.tail_len < 32 {
+                return;
+            }
+
+            let stripe = self.tail;
+            self.stripe(&stripe);
+            self.tail_len = 0;
+        }
+
+        let stripes = msg.chunks_exact(32);
+        let rest = stripes.remainder();
+
+        for stripe in stripes {
+            self.stripe(stripe);
+        }
+
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+    }
+
+    le_integer_writes!();
+
+    fn finish(&self) -> u64 {
+        let mut hash = if self.len >= 32 {
+            let [v1, v2, v3, v4] = self.acc;
+            let mut hash = v1.rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18));
+
+            for v in self.acc.iter() {
+                hash = xx_merge(hash, *v);
+            }
+
+            hash
+        } else {
+            self.seed.wrapping_add(XX_PRIME_5)
+        };
+
+        hash = hash.wrapping_add(self.len);
+
+        let mut tail = &self.tail[..self.tail_len];
+
+        while tail.len() >= 8 {
+            hash ^= xx_round(0, read_u64(&tail[..8]));
+            hash = hash.rotate_left(27).wrapping_mul(XX_PRIME_1).wrapping_add(XX_PRIME_4);
+            tail = &tail[8..];
+        }
+
+        if tail.len() >= 4 {
+            hash ^= (read_u32(&tail[..4]) as u64).wrapping_mul(XX_PRIME_1);
+            hash = hash.rotate_left(23).wrapping_mul(XX_PRIME_2).wrapping_add(XX_PRIME_3);
+            tail = &tail[4..];
+        }
+
+        for b in tail {
+            hash ^= (*b as u64).wrapping_mul(XX_PRIME_5);
+            hash = hash.rotate_left(11).wrapping_mul(XX_PRIME_1);
+        }
+
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(XX_PRIME_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(XX_PRIME_3);
+        hash ^= hash >> 32;
+        hash
+    }
+}
+
+#[inline]
+fn xx_round(acc: u64, lane: u64) -> u64 {
+    acc.wrapping_add(lane.wrapping_mul(XX_PRIME_2))
+        .rotate_left(31)
+        .wrapping_mul(XX_PRIME_1)
+}
+
+#[inline]
+fn xx_merge(hash: u64, acc: u64) -> u64 {
+    (hash ^ xx_round(0, acc)).wrapping_mul(XX_PRIME_1).wrapping_add(XX_PRIME_4)
+}
+
+/// xxHash64 of `key`.
+pub fn xxhash64(key: &[u8], seed: u64) -> u64 {
+    let mut hasher = XxHash64::with_seed(seed);
+    hasher.write(key);
+    hasher.finish()
+}
+
+/// Builds fresh XxHash64 hashers.
+///
+/// # Examples
+///
+/// ```
+/// use fastmap::FastMap;
+/// use fastmap::hashers::BuildXxHash64;
+///
+/// let mut map: FastMap<String, u64, _> = FastMap::with_hasher(BuildXxHash64::new());
+/// map.insert("Eat my shorts".to_string(), 42);
+/// assert_eq!(*map.get("Eat my shorts").unwrap(), 42);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildXxHash64 {
+    seed: u64
+}
+
+impl BuildXxHash64 {
+    pub fn new() -> BuildXxHash64 {
+        BuildXxHash64::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> BuildXxHash64 {
+        BuildXxHash64 { seed }
+    }
+
+    /// The seed handed to every XxHash64 this builds.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl BuildHasher for BuildXxHash64 {
+    type Hasher = XxHash64;
+    fn build_hasher(&self) -> Self::Hasher {
+        XxHash64::with_seed(self.seed)
+    }
+}
+
+
+// ***** FibonacciHasher *****
+
+// 2^64 divided by the golden ratio.
+const FIBONACCI: u64 = 11400714819323198485;
+
+/// Multiply-shift hasher for integer keys, build one per key through `BuildFibonacciHasher`.
+///
+/// Each integer write is a single xor and multiply by 2^64 / phi. The well mixed bits of
+/// the product are the high ones, so `finish` reverses its bytes to put them where FastMap
+/// masks out the bucket index. Byte slices are folded in eight bytes at a time, which works
+/// but gives a weak hash, prefer Murmur2_64a or xxHash64 for string keys.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FibonacciHasher {
+    hash: u64
+}
+
+impl FibonacciHasher {
+    pub fn new() -> FibonacciHasher {
+        FibonacciHasher::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> FibonacciHasher {
+        FibonacciHasher { hash: seed }
+    }
+
+    #[inline]
+    fn mix(&mut self, k: u64) {
+        self.hash = (self.hash ^ k).wrapping_mul(FIBONACCI);
+    }
+}
+
+impl Hasher for FibonacciHasher {
+    #[inline]
+    fn write(&mut self, msg: &[u8]) {
+        for chunk in msg.chunks(8) {
+            self.mix(read_u64(chunk));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.mix(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.mix(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.mix(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.mix(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.mix(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash.swap_bytes()
+    }
+}
+
+/// Builds fresh FibonacciHasher hashers.
+///
+/// # Examples
+///
+/// ```
+/// use fastmap::FastMap;
+/// use fastmap::hashers::BuildFibonacciHasher;
+///
+/// let mut map: FastMap<u64, u64, _> = FastMap::with_hasher(BuildFibonacciHasher::new());
+/// map.insert(21, 42);
+/// assert_eq!(*map.get(&21).unwrap(), 42);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildFibonacciHasher {
+    seed: u64
+}
+
+impl BuildFibonacciHasher {
+    pub fn new() -> BuildFibonacciHasher {
+        BuildFibonacciHasher::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> BuildFibonacciHasher {
+        BuildFibonacciHasher { seed }
+    }
+
+    /// The seed handed to every FibonacciHasher this builds.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl BuildHasher for BuildFibonacciHasher {
+    type Hasher = FibonacciHasher;
+    fn build_hasher(&self) -> Self::Hasher {
+        FibonacciHasher::with_seed(self.seed)
+    }
+}
@@ -0,0 +1,793 @@
+//! An insertion-ordered variant of `FastMap`.
+//!
+//! Entries live in a dense vector in the order they were inserted, together with their
+//! cached hash. The hash table only holds indices into that vector, probed linearly like
+//! `FastMap` but without tombstones: removes shift the following indices back. Iteration
+//! walks the entries vector, so it is deterministic and unaffected by resizes.
+
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+use std::iter::{FromIterator, FusedIterator};
+use std::mem;
+use std::slice::Iter as SliceIter;
+use std::slice::IterMut as SliceIterMut;
+use std::vec::IntoIter as VecIntoIter;
+
+use {BuildMurmur2_64a, CheckPolicy, FastMapConfig};
+
+// Marks a free slot in the index table.
+const EMPTY: usize = usize::MAX;
+
+/// A hash map that keeps its entries in insertion order.
+///
+/// Entries can also be reached by their position in that order.
+///
+/// # Examples
+///
+/// ```
+/// use fastmap::FastIndexMap;
+///
+/// let mut map = FastIndexMap::new();
+/// map.insert("b", 2);
+/// map.insert("a", 1);
+/// map.insert("c", 3);
+///
+/// assert_eq!(map.keys().cloned().collect::<Vec<_>>(), ["b", "a", "c"]);
+/// assert_eq!(map.get_index(1), Some((&"a", &1)));
+/// assert_eq!(map.get_index_of(&"c"), Some(2));
+/// ```
+pub struct FastIndexMap<K: Eq + Hash, V, S = BuildMurmur2_64a> {
+    indices: Vec<usize>,
+    entries: Vec<Bucket<K, V>>,
+    size: u32,
+    mod_mask: u64,
+    grow_at: usize,
+    headroom: usize,
+    config: FastMapConfig,
+    hasher: S,
+}
+
+struct Bucket<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+impl<K, V> FastIndexMap<K, V>
+    where K: Eq + Hash {
+    /// Creates a new FastIndexMap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastIndexMap;
+    ///
+    /// let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+    /// ```
+    pub fn new() -> Self {
+        FastIndexMap::with_capacity(4)
+    }
+
+
+    /// Creates a new FastIndexMap with a at least capacity, all sizes is a power of 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastIndexMap;
+    ///
+    /// let mut map: FastIndexMap<u64, u64> = FastIndexMap::with_capacity(20);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        FastIndexMap::with_capacity_and_hasher(capacity, BuildMurmur2_64a::new())
+    }
+
+
+    /// Creates a new FastIndexMap tuned by `config`, see `with_config_and_hasher`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::{FastIndexMap, FastMapConfig};
+    ///
+    /// let mut map: FastIndexMap<u64, u64> = FastIndexMap::with_config(FastMapConfig::new().max_load_factor(0.5));
+    /// ```
+    pub fn with_config(config: FastMapConfig) -> Self {
+        FastIndexMap::with_config_and_hasher(config, BuildMurmur2_64a::new())
+    }
+}
+
+impl<K, V, S> FastIndexMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher {
+    /// Creates a new FastIndexMap which will use the given hash builder to hash keys.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        FastIndexMap::with_capacity_and_hasher(4, hash_builder)
+    }
+
+
+    /// Creates a new FastIndexMap with at least capacity, using the given hash builder to hash keys.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        FastIndexMap::with_config_and_hasher(FastMapConfig::new().capacity(capacity), hash_builder)
+    }
+
+
+    /// Creates a new FastIndexMap tuned by `config`, using the given hash builder to hash keys.
+    ///
+    /// The load factors, growth factor and check policy apply to the index table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config` asks for incremental resizing, the index table is always
+    /// rebuilt in one go.
+    pub fn with_config_and_hasher(config: FastMapConfig, hash_builder: S) -> Self {
+        assert!(config.resize_step == 0, "FastIndexMap doesn't resize incrementally");
+
+        let capacity = config.capacity;
+        let mut map = FastIndexMap {
+            indices: Vec::new(),
+            entries: Vec::new(),
+            size: 0,
+            mod_mask: 0,
+            grow_at: 0,
+            headroom: 0,
+            config,
+            hasher: hash_builder,
+        };
+
+        let size = match capacity.checked_next_power_of_two() {
+            Some(lim) => lim.trailing_zeros().max(1),
+            None => panic!("capacity overflow"),
+        };
+        map.resize(size);
+
+        map
+    }
+
+    /// Returns a reference to the map's hash builder.
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    /// Returns the configuration the map was created with.
+    pub fn config(&self) -> &FastMapConfig {
+        &self.config
+    }
+
+
+    /// Reserves room for at least `additional` more entries, so they can be inserted
+    /// without the table growing.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.entries.len().checked_add(additional).expect("capacity overflow");
+
+        if needed > self.grow_at {
+            let size = self.size_for(needed).max(self.size + self.config.growth_shift);
+            self.resize(size);
+        }
+
+        self.entries.reserve(additional);
+    }
+
+
+    /// Insert key/value into the FastIndexMap.
+    ///
+    /// A new key goes last in the order. If the key was already present its value is
+    /// replaced in place and the old value returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastIndexMap;
+    ///
+    /// let mut map = FastIndexMap::new();
+    /// assert_eq!(map.insert(21, "Eat my shorts"), None);
+    /// assert_eq!(map.insert(21, "Don't have a cow"), Some("Eat my shorts"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.check_load();
+
+        let hash = self.hasher.hash_one(&key);
+
+        match self.find(hash, &key) {
+            Ok(pos) => {
+                let ix = self.indices[pos];
+                Some(mem::replace(&mut self.entries[ix].value, value))
+            }
+            Err(pos) => {
+                self.indices[pos] = self.entries.len();
+                self.entries.push(Bucket { hash, key, value });
+                None
+            }
+        }
+    }
+
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastIndexMap;
+    ///
+    /// let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+    /// map.insert(21, 42);
+    /// assert_eq!(map.get(&21), Some(&42));
+    /// assert_eq!(map.get(&42), None);
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let ix = self.get_index_of(key)?;
+        Some(&self.entries[ix].value)
+    }
+
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let ix = self.get_index_of(key)?;
+        Some(&mut self.entries[ix].value)
+    }
+
+
+    /// Returns the position of the key in the insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastIndexMap;
+    ///
+    /// let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+    /// map.insert(21, 42);
+    /// map.insert(7, 14);
+    /// assert_eq!(map.get_index_of(&7), Some(1));
+    /// assert_eq!(map.get_index_of(&42), None);
+    /// ```
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.hasher.hash_one(key);
+        let pos = self.find(hash, key).ok()?;
+        Some(self.indices[pos])
+    }
+
+
+    /// Returns the key and value at position `index` in the insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastIndexMap;
+    ///
+    /// let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+    /// map.insert(21, 42);
+    /// assert_eq!(map.get_index(0), Some((&21, &42)));
+    /// assert_eq!(map.get_index(1), None);
+    /// ```
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.entries.get(index).map(|b| (&b.key, &b.value))
+    }
+
+
+    /// Returns the key and a mutable value at position `index` in the insertion order.
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        self.entries.get_mut(index).map(|b| (&b.key, &mut b.value))
+    }
+
+
+    /// Removes a key from the map by swapping the last entry into its place, returning its
+    /// value if it was there.
+    ///
+    /// Takes constant time but moves the last entry out of order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastIndexMap;
+    ///
+    /// let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+    /// for i in 0..4 {
+    ///     map.insert(i, i);
+    /// }
+    /// assert_eq!(map.swap_remove(&1), Some(1));
+    /// assert_eq!(map.keys().cloned().collect::<Vec<_>>(), [0, 3, 2]);
+    /// ```
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.hasher.hash_one(key);
+        let pos = self.find(hash, key).ok()?;
+        let ix = self.indices[pos];
+
+        self.remove_pos(pos);
+        let bucket = self.entries.swap_remove(ix);
+
+        // Point the index of the entry that was last at its new place.
+        if ix < self.entries.len() {
+            let last = self.entries.len();
+            let pos = self.find_index(self.entries[ix].hash, last);
+            self.indices[pos] = ix;
+        }
+
+        self.ensure_min_load();
+        Some(bucket.value)
+    }
+
+
+    /// Removes a key from the map by shifting all following entries down, returning its
+    /// value if it was there.
+    ///
+    /// Keeps the order of the remaining entries, but takes time linear in the map size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastIndexMap;
+    ///
+    /// let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+    /// for i in 0..4 {
+    ///     map.insert(i, i);
+    /// }
+    /// assert_eq!(map.shift_remove(&1), Some(1));
+    /// assert_eq!(map.keys().cloned().collect::<Vec<_>>(), [0, 2, 3]);
+    /// ```
+    pub fn shift_remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.hasher.hash_one(key);
+        let pos = self.find(hash, key).ok()?;
+        let ix = self.indices[pos];
+
+        self.remove_pos(pos);
+        let bucket = self.entries.remove(ix);
+
+        for i in self.indices.iter_mut() {
+            if *i != EMPTY && *i > ix {
+                *i -= 1;
+            }
+        }
+
+        self.ensure_min_load();
+        Some(bucket.value)
+    }
+
+
+    /// Sorts the entries by key, changing the iteration order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastIndexMap;
+    ///
+    /// let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+    /// for i in [3, 1, 2].iter() {
+    ///     map.insert(*i, *i);
+    /// }
+    /// map.sort_keys();
+    /// assert_eq!(map.keys().cloned().collect::<Vec<_>>(), [1, 2, 3]);
+    /// assert_eq!(map.get_index_of(&3), Some(2));
+    /// ```
+    pub fn sort_keys(&mut self)
+        where K: Ord {
+        self.entries.sort_by(|a, b| a.key.cmp(&b.key));
+        self.rebuild_indices();
+    }
+
+
+    /// Returns true if the key is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.get_index_of(key).is_some()
+    }
+
+
+    /// Removes all entries, keeping the allocated table.
+    pub fn clear(&mut self) {
+        for i in self.indices.iter_mut() {
+            *i = EMPTY;
+        }
+
+        self.entries.clear();
+    }
+
+
+    /// Returns true if map is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+
+    //**** Iterators *****
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            outer: self.entries.iter()
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            outer: self.entries.iter_mut()
+        }
+    }
+
+
+    //**** Internal hash stuff *****
+
+    /// Makes room for the next insert, following the configured check policy.
+    #[inline]
+    fn check_load(&mut self) {
+        match self.config.check_policy {
+            CheckPolicy::EveryInsert => self.reserve(1),
+            CheckPolicy::Amortized(n) => {
+                // Rebuilding the table zeroes the headroom, forcing a new check.
+                if self.headroom == 0 {
+                    let n = n.min(self.grow_at.max(1));
+                    self.reserve(n);
+                    self.headroom = n;
+                }
+
+                self.headroom -= 1;
+            }
+        }
+    }
+
+    /// Finds the index slot pointing at `key`, or else the free slot that ended the probe.
+    #[inline]
+    fn find<Q>(&self, hash: u64, key: &Q) -> Result<usize, usize>
+        where K: Borrow<Q>, Q: Eq + ?Sized {
+        let mut pos = self.ix(hash);
+
+        loop {
+            match self.indices[pos] {
+                EMPTY => return Err(pos),
+                ix => {
+                    let bucket = &self.entries[ix];
+
+                    if bucket.hash == hash && bucket.key.borrow() == key {
+                        return Ok(pos);
+                    }
+                }
+            }
+
+            pos = self.next_ix(pos);
+        }
+    }
+
+
+    /// Number of elements in map.
+    ///
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+
+    pub fn load_rate(&self) -> f64 {
+        (self.entries.len() as f64) / (self.indices.len() as f64) * 100f64
+    }
+
+
+    /// Total number of slots available.
+    ///
+    pub fn capacity(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+
+impl<K, V, S> FastIndexMap<K, V, S>
+    where K: Eq + Hash {
+    #[inline]
+    fn ix(&self, hash: u64) -> usize {
+        (hash & self.mod_mask) as usize
+    }
+
+    #[inline]
+    fn next_ix(&self, ix: usize) -> usize {
+        (ix + 1) & (self.mod_mask as usize)
+    }
+
+    /// Finds the index slot pointing at entry `ix`, whose cached hash is `hash`.
+    fn find_index(&self, hash: u64, ix: usize) -> usize {
+        let mut pos = self.ix(hash);
+
+        while self.indices[pos] != ix {
+            pos = self.next_ix(pos);
+        }
+
+        pos
+    }
+
+    /// Frees index slot `pos`, shifting back the following indices that may move closer to home.
+    fn remove_pos(&mut self, mut hole: usize) {
+        self.indices[hole] = EMPTY;
+        let mut next = self.next_ix(hole);
+
+        while self.indices[next] != EMPTY {
+            let home = self.ix(self.entries[self.indices[next]].hash);
+
+            // Moving into the hole keeps it reachable unless its home lies between the two.
+            if (next.wrapping_sub(home) & self.mod_mask as usize) >= (next.wrapping_sub(hole) & self.mod_mask as usize) {
+                self.indices[hole] = self.indices[next];
+                self.indices[next] = EMPTY;
+                hole = next;
+            }
+
+            next = self.next_ix(next);
+        }
+    }
+
+    /// Points the index table at every entry again, using the cached hashes.
+    fn rebuild_indices(&mut self) {
+        for i in self.indices.iter_mut() {
+            *i = EMPTY;
+        }
+
+        for ix in 0..self.entries.len() {
+            let mut pos = self.ix(self.entries[ix].hash);
+
+            while self.indices[pos] != EMPTY {
+                pos = self.next_ix(pos);
+            }
+
+            self.indices[pos] = ix;
+        }
+    }
+
+    /// Smallest table size that holds `entries` under the load limit.
+    fn size_for(&self, entries: usize) -> u32 {
+        let mut size = 1;
+
+        while self.config.max_load(1 << size) < entries {
+            size += 1;
+
+            if size >= usize::BITS {
+                panic!("capacity overflow");
+            }
+        }
+
+        size
+    }
+
+    /// Halves the table once the live entries fall below the min load factor.
+    fn ensure_min_load(&mut self) {
+        if self.size > 1 && (self.entries.len() as f64) < self.indices.len() as f64 * self.config.min_load_factor {
+            let size = self.size - 1;
+            self.resize(size);
+        }
+    }
+
+    fn resize(&mut self, size: u32) {
+        let lim = 1usize << size;
+
+        self.size = size;
+        self.mod_mask = (lim as u64) - 1;
+        self.grow_at = self.config.max_load(lim);
+        self.headroom = 0;
+
+        self.indices = vec![EMPTY; lim];
+        self.rebuild_indices();
+    }
+}
+
+
+impl<K, V, S> Extend<(K, V)> for FastIndexMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher {
+    /// Inserts all pairs in order, reserving room for the iterator's lower size bound first.
+    ///
+    /// A key already in the map keeps its position and gets the new value.
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+
+        // Keys already in the map don't take new slots, so only count on half of them being new.
+        let hint = iter.size_hint().0;
+        self.reserve(if self.is_empty() { hint } else { hint.div_ceil(2) });
+
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<'a, K, V, S> Extend<(&'a K, &'a V)> for FastIndexMap<K, V, S>
+    where K: Eq + Hash + Copy, V: Copy, S: BuildHasher {
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|(&k, &v)| (k, v)));
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for FastIndexMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher + Default {
+    /// Builds a map from key/value pairs in iteration order. A repeated key keeps the
+    /// position of its first pair and the value of its last.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastIndexMap;
+    ///
+    /// let map: FastIndexMap<&str, u64> = vec![("b", 1), ("a", 2), ("b", 3)].into_iter().collect();
+    /// assert_eq!(map.iter().collect::<Vec<_>>(), [(&"b", &3), (&"a", &2)]);
+    /// ```
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = FastIndexMap::with_hasher(S::default());
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, S, const N: usize> From<[(K, V); N]> for FastIndexMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher + Default {
+    fn from(arr: [(K, V); N]) -> Self {
+        IntoIterator::into_iter(arr).collect()
+    }
+}
+
+impl<K, V, S> Default for FastIndexMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher + Default {
+    fn default() -> Self {
+        FastIndexMap::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> IntoIterator for FastIndexMap<K, V, S>
+    where K: Eq + Hash {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    /// Consumes the map, yielding its entries in order.
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter { outer: self.entries.into_iter() }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a FastIndexMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut FastIndexMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+
+// ***************** Iter *********************
+
+pub struct Iter<'a, K: 'a, V: 'a> {
+    outer: SliceIter<'a, Bucket<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline] fn next(&mut self) -> Option<(&'a K, &'a V)> { self.outer.next().map(|b| (&b.key, &b.value)) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.outer.size_hint() }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    #[inline] fn next_back(&mut self) -> Option<(&'a K, &'a V)> { self.outer.next_back().map(|b| (&b.key, &b.value)) }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> Clone for Iter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Iter { outer: self.outer.clone() }
+    }
+}
+
+
+// ***************** Iter Mut *********************
+
+pub struct IterMut<'a, K: 'a, V: 'a> {
+    outer: SliceIterMut<'a, Bucket<K, V>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline] fn next(&mut self) -> Option<(&'a K, &'a mut V)> { self.outer.next().map(|b| (&b.key, &mut b.value)) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.outer.size_hint() }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    #[inline] fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> { self.outer.next_back().map(|b| (&b.key, &mut b.value)) }
+}
+
+impl<'a, K, V> ExactSizeIterator for IterMut<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for IterMut<'a, K, V> {}
+
+
+// ***************** Into Iter *********************
+
+pub struct IntoIter<K, V> {
+    outer: VecIntoIter<Bucket<K, V>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    #[inline] fn next(&mut self) -> Option<(K, V)> { self.outer.next().map(|b| (b.key, b.value)) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.outer.size_hint() }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    #[inline] fn next_back(&mut self) -> Option<(K, V)> { self.outer.next_back().map(|b| (b.key, b.value)) }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+
+// ***************** Values Iter *********************
+
+pub struct Values<'a, K: 'a, V: 'a> {
+    inner: Iter<'a, K, V>
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    #[inline] fn next(&mut self) -> Option<&'a V> { self.inner.next().map(|kv| kv.1) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Values<'a, K, V> {
+    #[inline] fn next_back(&mut self) -> Option<&'a V> { self.inner.next_back().map(|kv| kv.1) }
+}
+
+impl<'a, K, V> ExactSizeIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> Clone for Values<'a, K, V> {
+    fn clone(&self) -> Self {
+        Values { inner: self.inner.clone() }
+    }
+}
+
+
+// ***************** Keys Iter *********************
+
+pub struct Keys<'a, K: 'a, V: 'a> {
+    inner: Iter<'a, K, V>
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    #[inline] fn next(&mut self) -> Option<&'a K> { self.inner.next().map(|kv| kv.0) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Keys<'a, K, V> {
+    #[inline] fn next_back(&mut self) -> Option<&'a K> { self.inner.next_back().map(|kv| kv.0) }
+}
+
+impl<'a, K, V> ExactSizeIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> Clone for Keys<'a, K, V> {
+    fn clone(&self) -> Self {
+        Keys { inner: self.inner.clone() }
+    }
+}
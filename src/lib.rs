@@ -1,26 +1,95 @@
+#[cfg(feature = "random")]
+extern crate getrandom;
+#[cfg(feature = "serde")]
+extern crate serde;
+
+use std::borrow::Borrow;
+use std::fmt;
 use std::hash::Hash;
 use std::hash::BuildHasher;
 use std::hash::Hasher;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Index;
+
+mod config;
+mod entry;
+mod error;
+pub mod hashers;
+pub mod index_map;
+pub mod robin_hood;
+#[cfg(feature = "serde")]
+pub mod serde_support;
+pub mod set;
+pub mod snapshot;
+pub mod storage;
+pub mod swiss;
+
+pub use config::{CheckPolicy, FastMapConfig};
+pub use entry::{Entry, OccupiedEntry, OccupiedError, VacantEntry};
+pub use error::{SnapshotError, TryReserveError};
+pub use index_map::FastIndexMap;
+pub use robin_hood::RobinHoodMap;
+pub use set::FastSet;
+pub use storage::{DefaultStorage, Interleaved, Split, Storage};
+pub use swiss::Swiss;
+
+use storage::Slot;
 
 // struct Kv<V> {
 //     key: u64,
 //     value: V
 // }
 
-pub struct FastMap<K: Eq + Hash, V, S = Murmur2_64a> {
-    cache:  Vec<Bucket<K, V>>,
+pub struct FastMap<K: Eq + Hash, V, S = BuildMurmur2_64a, T = DefaultStorage<K, V>>
+    where T: Storage<K, V> {
+    cache:  T,
+    // Buckets still to move over while resizing incrementally, empty otherwise.
+    old: T,
+    migrated: usize,
     // indices:  Vec<(K, V)>,
     size: u32,
     mod_mask: u64,
     count: usize,
+    deleted: usize,
+    grow_at: usize,
+    headroom: usize,
+    config: FastMapConfig,
     hasher: S,
+    entries: PhantomData<(K, V)>,
 }
 
-enum Bucket<K: Eq + Hash, V> {
-    Value(u64, K, V),
-    Deleted,
-    Empty,
-}
+/// A FastMap keeping hashes, keys and values in separate arrays, see `storage::Split`.
+///
+/// Probing only walks the array of cached hashes, which pays off for large values.
+///
+/// # Examples
+///
+/// ```
+/// use fastmap::{FastMap, SplitMap};
+///
+/// let mut map: SplitMap<u64, [u64; 32]> = FastMap::new_split();
+/// map.insert(21, [42; 32]);
+/// assert_eq!(map.get(&21).unwrap()[0], 42);
+/// ```
+pub type SplitMap<K, V, S = BuildMurmur2_64a> = FastMap<K, V, S, Split<K, V>>;
+
+/// A FastMap probing a group of control bytes at a time, see `swiss::Swiss`.
+///
+/// Lookups mostly read the control bytes, a byte per bucket, and only touch the slots whose
+/// tag matches.
+///
+/// # Examples
+///
+/// ```
+/// use fastmap::{FastMap, SwissMap};
+///
+/// let mut map: SwissMap<u64, &str> = FastMap::new_swiss();
+/// map.insert(21, "Eat my shorts");
+/// assert_eq!(map.get(&21), Some(&"Eat my shorts"));
+/// ```
+pub type SwissMap<K, V, S = BuildMurmur2_64a> = FastMap<K, V, S, Swiss<K, V>>;
 
 impl<K, V> FastMap<K, V>
     where K: Eq + Hash {
@@ -48,77 +117,373 @@ impl<K, V> FastMap<K, V>
     /// let mut map: FastMap<u64, u64> = FastMap::with_capacity(20);
     /// ```
     pub fn with_capacity(capacity: usize) -> Self {
-        let m = Murmur2_64a::new();
+        FastMap::with_capacity_and_hasher(capacity, BuildMurmur2_64a::new())
+    }
+
+
+    /// Creates a new FastMap tuned by `config`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::{FastMap, FastMapConfig};
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::with_config(FastMapConfig::new().max_load_factor(0.5));
+    /// ```
+    pub fn with_config(config: FastMapConfig) -> Self {
+        FastMap::with_config_and_hasher(config, BuildMurmur2_64a::new())
+    }
+}
+
+#[cfg(feature = "random")]
+impl<K, V> FastMap<K, V, RandomMurmur>
+    where K: Eq + Hash {
+    /// Creates a new FastMap hashing with a Murmur2_64a seed drawn from OS entropy.
+    ///
+    /// Use this for maps keyed by untrusted input, it makes precomputed collisions useless.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map = FastMap::new_randomized();
+    /// map.insert(21, "Eat my shorts");
+    /// ```
+    pub fn new_randomized() -> Self {
+        FastMap::with_hasher(RandomMurmur::new())
+    }
+}
+
+impl<K, V> FastMap<K, V, BuildMurmur2_64a, Split<K, V>>
+    where K: Eq + Hash {
+    /// Creates a new FastMap with hashes, keys and values in separate arrays.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::SplitMap;
+    ///
+    /// let mut map: SplitMap<u64, u64> = SplitMap::new_split();
+    /// map.insert(21, 42);
+    /// ```
+    pub fn new_split() -> Self {
+        FastMap::with_hasher(BuildMurmur2_64a::new())
+    }
+}
+
+impl<K, V> FastMap<K, V, BuildMurmur2_64a, Swiss<K, V>>
+    where K: Eq + Hash {
+    /// Creates a new FastMap probing SIMD-scanned control bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::SwissMap;
+    ///
+    /// let mut map: SwissMap<u64, u64> = SwissMap::new_swiss();
+    /// map.insert(21, 42);
+    /// ```
+    pub fn new_swiss() -> Self {
+        FastMap::with_hasher(BuildMurmur2_64a::new())
+    }
+}
+
+impl<K, V, S, T> FastMap<K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher, T: Storage<K, V> {
+    /// Creates a new FastMap which will use the given hash builder to hash keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let mut map: FastMap<u64, u64, RandomState> = FastMap::with_hasher(RandomState::new());
+    /// map.insert(21, 42);
+    /// ```
+    pub fn with_hasher(hash_builder: S) -> Self {
+        FastMap::with_capacity_and_hasher(4, hash_builder)
+    }
+
+
+    /// Creates a new FastMap with at least capacity, using the given hash builder to hash keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let mut map: FastMap<u64, u64, RandomState> = FastMap::with_capacity_and_hasher(20, RandomState::new());
+    /// map.insert(21, 42);
+    /// ```
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        FastMap::with_config_and_hasher(FastMapConfig::new().capacity(capacity), hash_builder)
+    }
+
 
+    /// Creates a new FastMap tuned by `config`, using the given hash builder to hash keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::{FastMap, FastMapConfig};
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let config = FastMapConfig::new().capacity(20).growth_factor(4);
+    /// let mut map: FastMap<u64, u64, RandomState> = FastMap::with_config_and_hasher(config, RandomState::new());
+    /// map.insert(21, 42);
+    /// ```
+    pub fn with_config_and_hasher(config: FastMapConfig, hash_builder: S) -> Self {
+        let capacity = config.capacity;
         let mut map = FastMap {
-            cache: Vec::new(),
+            cache: T::new(),
+            old: T::new(),
+            migrated: 0,
             size: 0,
             count: 0,
+            deleted: 0,
+            grow_at: 0,
+            headroom: 0,
+            config,
             mod_mask: 0,
-            hasher: m,
+            hasher: hash_builder,
+            entries: PhantomData,
         };
 
-        map.increase_cache();
-
-        while map.lim() < capacity {
-            map.increase_cache();
-        }
+        let size = match capacity.checked_next_power_of_two() {
+            Some(lim) => lim.trailing_zeros().max(1),
+            None => TryReserveError::CapacityOverflow.bail(),
+        };
+        map.resize(size);
 
         // flame::clear();
         map
     }
 
+    /// Returns a reference to the map's hash builder.
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    /// Returns the configuration the map was created with.
+    pub fn config(&self) -> &FastMapConfig {
+        &self.config
+    }
+
+
+    /// Reserves room for at least `additional` more entries, so they can be inserted
+    /// without the table growing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new table size overflows, and aborts if the allocation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// map.reserve(1000);
+    ///
+    /// let capacity = map.capacity();
+    /// for i in 0..1000 {
+    ///     map.insert(i, i);
+    /// }
+    /// assert_eq!(map.capacity(), capacity);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(err) = self.try_reserve(additional) {
+            err.bail();
+        }
+    }
+
+    /// Tries to reserve room for at least `additional` more entries, so they can be
+    /// inserted without the table growing.
+    ///
+    /// The table is grown in a single allocation. If the size overflows or the allocator
+    /// fails an error is returned and the map is left as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::{FastMap, TryReserveError};
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// assert_eq!(map.try_reserve(1000), Ok(()));
+    /// assert_eq!(map.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.try_ensure_load_rate(additional)
+    }
+
 
     /// Insert key/value into the FastMap.
     ///
+    /// If the key was already present its value is replaced and the old value returned.
+    ///
     /// # Examples
     ///
     /// ```
     /// use fastmap::FastMap;
     ///
     /// let mut map = FastMap::new();
-    /// map.insert(21, "Eat my shorts");
+    /// assert_eq!(map.insert(21, "Eat my shorts"), None);
+    /// assert_eq!(map.insert(21, "Don't have a cow"), Some("Eat my shorts"));
     /// ```
-    pub fn insert(&mut self, key: K, value: V) -> bool {
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.check_load();
+        self.migrate_step();
+
+        let (hash, ix) = self.calc_index(&key);
+
+        match self.probe(hash, &key, ix) {
+            Ok(ix) => Some(mem::replace(self.cache.value_mut(ix), value)),
+            Err(ix) => match self.probe_old(hash, &key) {
+                Some(old_ix) => Some(mem::replace(self.old.value_mut(old_ix), value)),
+                None => {
+                    self.insert_at(ix, hash, key, value);
+                    None
+                }
+            },
+        }
+    }
 
-        self.insert_internal(key, value);
 
-        if (self.count & 4) == 4 {
-            self.ensure_load_rate();
-        }
+    /// Like `insert`, but an equal key already present is swapped for `key` in its
+    /// bucket and handed back together with the old value.
+    pub(crate) fn replace_key(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.check_load();
+        self.migrate_step();
 
-        true
-    }
+        let (hash, ix) = self.calc_index(&key);
 
-    fn insert_internal(&mut self, key: K, value: V) -> bool {
-        // let _guard = flame::start_guard("insert");
-        let (hash, mut ix) = self.calc_index(&key);
-        // let ix_start = ix;
-
-        {
-            loop {
-                match self.cache[ix] {
-                    Bucket::Value(h, ref k, _) => {
-                        if h == hash && *k == key {
-                            return false;
-                        } else {
-                            ix += 1;
-                        }
-                    }
-                    Bucket::Deleted => ix += 1,
-                    Bucket::Empty => break, // Got free spot!
-                }
+        match self.probe(hash, &key, ix) {
+            Ok(ix) => {
+                let (hash, old_key, old_value) = self.cache.take(ix);
+                self.cache.put(ix, hash, key, value);
+                Some((old_key, old_value))
             }
+            Err(ix) => match self.probe_old(hash, &key) {
+                Some(old_ix) => {
+                    let (hash, old_key, old_value) = self.old.take(old_ix);
+                    self.old.put(old_ix, hash, key, value);
+                    Some((old_key, old_value))
+                }
+                None => {
+                    self.insert_at(ix, hash, key, value);
+                    None
+                }
+            },
+        }
+    }
+
+    /// Insert key/value into the FastMap, reporting a failure to grow the table instead
+    /// of aborting.
+    ///
+    /// Like `insert`, but the table is only grown when the key is new, and if that
+    /// growth overflows or the allocator fails the error is returned and the map is left
+    /// as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map = FastMap::new();
+    /// assert_eq!(map.insert_fallible(21, "Eat my shorts"), Ok(None));
+    /// assert_eq!(map.insert_fallible(21, "Don't have a cow"), Ok(Some("Eat my shorts")));
+    /// ```
+    pub fn insert_fallible(&mut self, key: K, value: V) -> Result<Option<V>, TryReserveError> {
+        self.migrate_step();
+
+        let (hash, ix) = self.calc_index(&key);
 
-            self.count += 1;
-            self.cache[ix] = Bucket::Value(hash, key, value);
+        if let Ok(ix) = self.probe(hash, &key, ix) {
+            return Ok(Some(mem::replace(self.cache.value_mut(ix), value)));
+        }
+        if let Some(old_ix) = self.probe_old(hash, &key) {
+            return Ok(Some(mem::replace(self.old.value_mut(old_ix), value)));
+        }
+
+        // Growing may rebuild the table, so look for a free bucket afterwards.
+        self.try_check_load()?;
+        self.insert_internal(hash, key, value);
+
+        Ok(None)
+    }
+
+    /// Insert key/value into the FastMap unless the key is already present.
+    ///
+    /// On success a mutable reference to the inserted value is returned. If the key
+    /// exists nothing is changed, and the error carries the occupied entry together
+    /// with the rejected value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map = FastMap::new();
+    /// assert_eq!(*map.try_insert(21, "Eat my shorts").unwrap(), "Eat my shorts");
+    ///
+    /// let err = map.try_insert(21, "Don't have a cow").unwrap_err();
+    /// assert_eq!(*err.entry.get(), "Eat my shorts");
+    /// assert_eq!(err.value, "Don't have a cow");
+    /// ```
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<&mut V, OccupiedError<'_, K, V, S, T>> {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+            Entry::Vacant(entry) => Ok(entry.insert(value)),
         }
+    }
 
-        true
+    /// Gets the given key's corresponding entry in the map for in-place manipulation.
+    ///
+    /// The probe sequence is only walked once, whether the key is present or not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<&str, u64> = FastMap::new();
+    ///
+    /// for word in "a b a c a b".split(' ') {
+    ///     *map.entry(word).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(*map.get(&"a").unwrap(), 3);
+    /// assert_eq!(*map.get(&"b").unwrap(), 2);
+    /// assert_eq!(*map.get(&"c").unwrap(), 1);
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S, T> {
+        // Grow up front so the slot we find stays valid for a vacant insert.
+        self.check_load();
+        self.migrate_step();
+
+        let (hash, ix) = self.calc_index(&key);
+
+        match self.probe(hash, &key, ix) {
+            Ok(ix) => Entry::Occupied(OccupiedEntry::new(self, ix)),
+            Err(ix) => match self.probe_old(hash, &key) {
+                // Move it over ahead of time, so entries only ever point into the new table.
+                Some(old_ix) => {
+                    let (hash, key, value) = self.take_old(old_ix);
+                    self.insert_at(ix, hash, key, value);
+                    Entry::Occupied(OccupiedEntry::new(self, ix))
+                }
+                None => Entry::Vacant(VacantEntry::new(self, hash, key, ix)),
+            },
+        }
     }
 
     /// Get value from the FastMap.
     ///
+    /// The key may be any borrowed form of the map's key type, e.g. `&str` for `String` keys.
+    ///
     /// # Examples
     ///
     /// ```
@@ -126,28 +491,44 @@ impl<K, V> FastMap<K, V>
     ///
     /// let mut map: FastMap<u64, u64> = FastMap::new();
     /// map.insert(21, 42);
-    /// let val = map.get(21);
+    /// let val = map.get(&21);
     /// assert!(val.is_some());
     /// assert_eq!(*val.unwrap(), 42);
-    /// assert!(map.contains_key(21));
-    /// ```
-    pub fn get(&self, key: K) -> Option<&V> {
-        let (hash, mut ix) = self.calc_index(&key);
-
-        loop {
-            match self.cache[ix] {
-                Bucket::Value(h, ref k, ref v) => {
-                    if h == hash && *k == key {
-                        return Some(&v);
-                    } else {
-                        ix += 1;
-                    }
-                }
-                Bucket::Deleted => ix += 1,
-                Bucket::Empty => return None,
-            }
+    /// assert!(map.contains_key(&21));
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let (hash, ix) = self.calc_index(key);
+
+        match self.probe(hash, key, ix) {
+            Ok(ix) => Some(self.cache.value(ix)),
+            Err(_) => Some(self.old.value(self.probe_old(hash, key)?)),
         }
+    }
 
+    /// Get the stored key and its value from the FastMap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<String, u64> = FastMap::new();
+    /// map.insert("Bart".to_string(), 10);
+    /// assert_eq!(map.get_key_value("Bart"), Some((&"Bart".to_string(), &10)));
+    /// assert_eq!(map.get_key_value("Lisa"), None);
+    /// ```
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let (hash, ix) = self.calc_index(key);
+
+        match self.probe(hash, key, ix) {
+            Ok(ix) => Some((self.cache.key(ix), self.cache.value(ix))),
+            Err(_) => {
+                let old_ix = self.probe_old(hash, key)?;
+                Some((self.old.key(old_ix), self.old.value(old_ix)))
+            }
+        }
     }
 
     /// Get mutable value from the FastMap.
@@ -160,37 +541,26 @@ impl<K, V> FastMap<K, V>
     /// let mut map: FastMap<u64, u64> = FastMap::new();
     /// map.insert(21, 42);
     ///
-    /// assert_eq!(*map.get(21).unwrap(), 42);
-    /// assert!(map.contains_key(21));
+    /// assert_eq!(*map.get(&21).unwrap(), 42);
+    /// assert!(map.contains_key(&21));
     ///
     /// {
-    ///     let mut val = map.get_mut(21).unwrap();
+    ///     let mut val = map.get_mut(&21).unwrap();
     ///     *val+=1;
     /// }
-    ///     assert_eq!(*map.get(21).unwrap(), 43);
-    /// ```
-    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
-        let (hash, mut ix) = self.calc_index(&key);
-
-        loop {
-            match self.cache[ix] {
-                Bucket::Value(h, ref k, _) => {
-                    if h == hash && *k == key {
-                        break;
-                    } else {
-                        ix += 1;
-                    }
-                }
-                Bucket::Deleted => ix += 1,
-                Bucket::Empty => return None,
+    ///     assert_eq!(*map.get(&21).unwrap(), 43);
+    /// ```
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let (hash, ix) = self.calc_index(key);
+
+        match self.probe(hash, key, ix) {
+            Ok(ix) => Some(self.cache.value_mut(ix)),
+            Err(_) => {
+                let old_ix = self.probe_old(hash, key)?;
+                Some(self.old.value_mut(old_ix))
             }
         }
-
-        if let Bucket::Value(_, _, ref mut v) = self.cache[ix] {
-            return Some(&mut *v);
-        } else {
-            panic!("get_mut item we want to give away were not there anymore!");
-        }
     }
 
     /// Remove value from the FastMap.
@@ -202,41 +572,45 @@ impl<K, V> FastMap<K, V>
     ///
     /// let mut map: FastMap<u64, u64> = FastMap::new();
     /// map.insert(21, 42);
-    /// let val = map.remove(21);
+    /// let val = map.remove(&21);
     /// assert!(val.is_some());
     /// assert_eq!(val.unwrap(), 42);
-    /// assert!(!map.contains_key(21));
+    /// assert!(!map.contains_key(&21));
     /// ```
-    pub fn remove(&mut self, key: K) -> Option<V> {
-        let (hash, mut ix) = self.calc_index(&key);
-
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
 
-        loop {
-            match self.cache[ix] {
-                Bucket::Value(h, ref k, _) => {
-                    if h == hash && *k == key {
-                        break;
-                    } else {
-                        ix += 1;
-                    }
-                }
-                Bucket::Deleted => ix += 1,
-                Bucket::Empty => return None,
+    /// Remove the stored key and its value from the FastMap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// map.insert(21, 42);
+    /// assert_eq!(map.remove_entry(&21), Some((21, 42)));
+    /// assert_eq!(map.remove_entry(&21), None);
+    /// ```
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.migrate_step();
+
+        let (hash, ix) = self.calc_index(key);
+
+        let entry = match self.probe(hash, key, ix) {
+            Ok(ix) => self.remove_at(ix),
+            Err(_) => {
+                let old_ix = self.probe_old(hash, key)?;
+                let (_, k, v) = self.take_old(old_ix);
+                (k, v)
             }
-        }
-
-        self.count -= 1;
-
-        // Push elemtent to switch with
-        self.cache.push(Bucket::Deleted);
-
-        let val = self.cache.swap_remove(ix);
-        self.cache[ix] = Bucket::Deleted;
+        };
 
-        match val {
-            Bucket::Value(_, _, v) => return Some(v),
-            _ => panic!("Item that we wanted to remove is gone!"),
-        }
+        self.ensure_min_load();
+        Some(entry)
     }
 
     /// Returns true if key is in map.
@@ -248,13 +622,11 @@ impl<K, V> FastMap<K, V>
     ///
     /// let mut map: FastMap<u64, u64> = FastMap::new();
     /// map.insert(21, 42);
-    /// assert!(map.contains_key(21));
+    /// assert!(map.contains_key(&21));
     /// ```
-    pub fn contains_key(&self, key: K) -> bool {
-        match self.get(key) {
-            Some(_) => true,
-            None    => false
-        }
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.get(key).is_some()
     }
 
 
@@ -271,11 +643,12 @@ impl<K, V> FastMap<K, V>
     /// assert_eq!(map.len(), 0);
     /// ```
     pub fn clear(&mut self) {
-        for i in 0..self.cache.len() {
-            self.cache[i] = Bucket::Empty;
-        }
+        self.cache.clear();
 
+        self.old = T::new();
+        self.migrated = 0;
         self.count = 0;
+        self.deleted = 0;
     }
 
     /// Returns true if map is empty
@@ -288,307 +661,1237 @@ impl<K, V> FastMap<K, V>
     /// let mut map: FastMap<u64, u64> = FastMap::new();
     /// map.insert(21, 42);
     /// assert!(!map.is_empty());
-    /// map.remove(21);
+    /// map.remove(&21);
     /// assert!(map.is_empty());
     /// ```
-    pub fn is_empty(&mut self) -> bool {
+    pub fn is_empty(&self) -> bool {
         self.count == 0
     }
 
 
     //**** Iterators *****
 
-    pub fn iter<'a>(&self) -> Iter<K, V> {
+    pub fn iter(&self) -> Iter<'_, K, V, T> {
         Iter {
-            outer: self.cache.iter()
+            outer: self.cache.iter().chain(self.old.iter()),
+            remaining: self.count,
         }
     }
 
-    pub fn keys(&mut self) -> Keys<K, V> {
+    pub fn keys(&self) -> Keys<'_, K, V, T> {
         Keys { inner: self.iter() }
     }
 
-    pub fn values(&mut self) -> Values<K, V> {
+    pub fn values(&self) -> Values<'_, K, V, T> {
         Values { inner: self.iter() }
     }
 
-    pub fn iter_mut<'a>(&mut self) -> IterMut<K, V> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V, T> {
         // IterMut::new(&mut self.cache)
         IterMut {
-            outer: self.cache.iter_mut()
+            outer: self.cache.iter_mut().chain(self.old.iter_mut()),
+            remaining: self.count,
         }
     }
 
-
-    //**** Internal hash stuff *****
-
-    // #[inline]
-    // fn hash_u64(seed: u64) -> u64 {
-    //     let a = 11400714819323198549u64;
-    //     let val = a.wrapping_mul(seed);
-    //     val
-    // }
-
-    #[inline]
-    fn calc_index(&self, key: &K) -> (u64, usize) {
-        let mut hasher = self.hasher.build_hasher();
-        key.hash(&mut hasher);
-        let hash = hasher.finish();
-
-        // Faster modulus
-        let ix = self.ix(hash);
-        (hash, ix)
+    /// Iterates over mutable references to the values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// map.insert(21, 42);
+    ///
+    /// for v in map.values_mut() {
+    ///     *v += 1;
+    /// }
+    /// assert_eq!(map.get(&21), Some(&43));
+    /// ```
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V, T> {
+        ValuesMut { inner: self.iter_mut() }
     }
 
-    #[inline]
-    fn ix(&self, hash: u64) -> usize {
-        (hash & self.mod_mask) as usize
+    /// Consumes the map, yielding its keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// map.insert(21, 42);
+    /// assert_eq!(map.into_keys().collect::<Vec<_>>(), [21]);
+    /// ```
+    pub fn into_keys(self) -> IntoKeys<K, V, T> {
+        IntoKeys { inner: self.into_iter() }
     }
 
-
-    #[inline]
-    fn lim(&self) -> usize {
-        2u64.pow(self.size) as usize
+    /// Consumes the map, yielding its values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// map.insert(21, 42);
+    /// assert_eq!(map.into_values().collect::<Vec<_>>(), [42]);
+    /// ```
+    pub fn into_values(self) -> IntoValues<K, V, T> {
+        IntoValues { inner: self.into_iter() }
     }
 
-
-    fn increase_cache(&mut self) {
-        // let _guard = flame::start_guard("increase_cache");
-        self.size += 1;
-        let new_lim = self.lim();
-        self.mod_mask = (new_lim as u64) - 1;
-
-        self.rebuild_cache();
-    }
+    /// Removes every entry, yielding them as key/value pairs. The table keeps its size.
+    ///
+    /// Entries the iterator doesn't get to are dropped when it is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// map.insert(21, 42);
+    ///
+    /// assert_eq!(map.drain().collect::<Vec<_>>(), [(21, 42)]);
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> Drain<'_, K, V, S, T> {
+        Drain { map: self, ix: 0 }
+    }
+
+    /// Keeps only the entries for which `f` returns true, walking the table in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// for i in 0..10 {
+    ///     map.insert(i, i);
+    /// }
+    ///
+    /// map.retain(|k, v| { *v += 1; k % 2 == 0 });
+    /// assert_eq!(map.len(), 5);
+    /// assert_eq!(map.get(&4), Some(&5));
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+        where F: FnMut(&K, &mut V) -> bool {
+        for ix in 0..self.buckets_len() {
+            if let Some((k, v)) = self.bucket_mut(ix) {
+                if !f(k, v) {
+                    self.take_bucket(ix);
+                }
+            }
+        }
+
+        // Only shrink once done, resizing mid-walk would move entries past the walk.
+        self.ensure_min_load();
+    }
+
+    /// Removes and yields the entries for which `pred` returns true, keeping the rest.
+    ///
+    /// Entries are only looked at as the iterator advances, dropping it early keeps
+    /// everything it didn't get to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// for i in 0..10 {
+    ///     map.insert(i, i);
+    /// }
+    ///
+    /// let mut odd: Vec<u64> = map.extract_if(|k, _| k % 2 == 1).map(|(k, _)| k).collect();
+    /// odd.sort();
+    /// assert_eq!(odd, [1, 3, 5, 7, 9]);
+    /// assert_eq!(map.len(), 5);
+    /// ```
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, F, S, T>
+        where F: FnMut(&K, &mut V) -> bool {
+        ExtractIf { map: self, ix: 0, pred }
+    }
+
+
+    //**** Internal hash stuff *****
+
+    #[inline]
+    fn calc_index<Q>(&self, key: &Q) -> (u64, usize)
+        where Q: Hash + ?Sized {
+        let hash = self.hasher.hash_one(key);
+
+        // Faster modulus
+        let ix = self.ix(hash);
+        (hash, ix)
+    }
+
+    /// Looks `key` up in the table, see `Storage::probe`.
+    #[inline]
+    fn probe<Q>(&self, hash: u64, key: &Q, ix: usize) -> Result<usize, usize>
+        where K: Borrow<Q>, Q: Eq + ?Sized {
+        self.cache.probe(hash, ix, |k| k.borrow() == key)
+    }
+
+    /// Looks `key` up among the buckets still to move while resizing incrementally.
+    #[inline]
+    fn probe_old<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+        where K: Borrow<Q>, Q: Eq + ?Sized {
+        if self.old.is_empty() {
+            return None;
+        }
+
+        let ix = (hash as usize) & (self.old.len() - 1);
+        self.old.probe(hash, ix, |k| k.borrow() == key).ok()
+    }
+
+
+    /// Number of elements in map.
+    ///
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+
+    /// Force count number of slots filled.
+    ///
+    pub fn load(&self) -> u64 {
+        (self.cache.iter().count() + self.old.iter().count()) as u64
+    }
+
+
+
+    pub fn load_rate(&self) -> f64 {
+        (self.count as f64) / (self.cache.len() as f64) * 100f64
+    }
+
+
+    /// Number of tombstones left behind by removed entries.
+    ///
+    pub fn tombstones(&self) -> usize {
+        self.deleted
+    }
+
+
+    /// Longest run of occupied or deleted buckets, the most an unsuccessful lookup can probe.
+    ///
+    pub fn max_probe_length(&self) -> usize {
+        let mut longest = 0;
+        let mut run = 0;
+
+        // Go around twice so runs wrapping past the end of the table are counted whole.
+        for ix in 0..self.cache.len() * 2 {
+            if let Slot::Empty = self.cache.slot(ix & (self.cache.len() - 1)) {
+                run = 0;
+            } else {
+                run += 1;
+                longest = longest.max(run);
+            }
+        }
+
+        longest.min(self.cache.len())
+    }
+
+
+    /// Total number of slots available.
+    ///
+    pub fn capacity(&self) -> usize {
+        self.cache.len()
+    }
+
+
+    /// True while an incremental resize still has buckets to move over.
+    ///
+    pub fn is_resizing(&self) -> bool {
+        !self.old.is_empty()
+    }
+
+
+    /// Shrinks the table as much as possible while keeping every entry under the load limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// for i in 0..1000 {
+    ///     map.insert(i, i);
+    /// }
+    /// for i in 10..1000 {
+    ///     map.remove(&i);
+    /// }
+    ///
+    /// map.shrink_to_fit();
+    /// assert_eq!(map.capacity(), 16);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+
+    /// Shrinks the table as much as possible while it still fits `min_capacity` entries
+    /// under the load limit, and at least the entries already in it. Never grows the table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::with_capacity(1000);
+    /// map.insert(21, 42);
+    ///
+    /// map.shrink_to(100);
+    /// assert_eq!(map.capacity(), 256);
+    /// map.shrink_to(1000);
+    /// assert_eq!(map.capacity(), 256);
+    /// ```
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let size = match self.size_for(self.count.max(min_capacity)) {
+            Ok(size) => size,
+            Err(_) => return,
+        };
+
+        if size < self.size {
+            self.resize(size);
+        }
+    }
+
+
+    pub fn assert_count(&self) -> bool {
+        self.count as u64 == self.load()
+    }
+
+
+    pub fn collisions(&self) -> FastMap<u64, u64> {
+        let map = FastMap::new();
+
+        // for s in self.cache.iter() {
+        //     let key = s.len() as u64;
+        //     if key > 0 {
+        //         if !map.contains_key(key) {
+        //             map.insert(key, 1);
+        //         } else {
+        //             let counter = map.get_mut(key).unwrap();
+        //             *counter += 1;
+        //         }
+        //     }
+        // }
+
+        // map.sort();
+
+        map
+    }
+}
+
+
+
+impl<K, V, S, T> FastMap<K, V, S, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    /// Puts a new entry into the free bucket `ix` found by `probe`.
+    fn insert_at(&mut self, ix: usize, hash: u64, key: K, value: V) {
+        if let Slot::Deleted = self.cache.slot(ix) {
+            self.deleted -= 1;
+        }
+
+        self.count += 1;
+        self.cache.put(ix, hash, key, value);
+    }
+
+    /// Takes the value out of bucket `ix`, leaving a tombstone behind.
+    fn remove_at(&mut self, ix: usize) -> (K, V) {
+        self.count -= 1;
+        self.deleted += 1;
+
+        let (_, k, v) = self.cache.take(ix);
+        (k, v)
+    }
+
+    /// Takes the entry out of old bucket `ix`, leaving a tombstone so later old probes still pass.
+    fn take_old(&mut self, ix: usize) -> (u64, K, V) {
+        self.count -= 1;
+        self.old.take(ix)
+    }
+
+    /// Number of buckets in the table and the old one together, see `bucket_mut`.
+    #[inline]
+    fn buckets_len(&self) -> usize {
+        self.cache.len() + self.old.len()
+    }
+
+    /// The entry in bucket `ix`, counting on into the old table past the end of the new one.
+    #[inline]
+    fn bucket_mut(&mut self, ix: usize) -> Option<(&K, &mut V)> {
+        let (table, ix) = match ix.checked_sub(self.cache.len()) {
+            None => (&mut self.cache, ix),
+            Some(old_ix) => (&mut self.old, old_ix),
+        };
+
+        match table.slot(ix) {
+            Slot::Full(_) => Some(table.entry_mut(ix)),
+            _ => None,
+        }
+    }
+
+    /// Takes the entry out of bucket `ix`, numbered like `bucket_mut`. Never resizes.
+    fn take_bucket(&mut self, ix: usize) -> (K, V) {
+        match ix.checked_sub(self.cache.len()) {
+            None => self.remove_at(ix),
+            Some(old_ix) => {
+                let (_, k, v) = self.take_old(old_ix);
+                (k, v)
+            }
+        }
+    }
+
+    /// Places a key known not to be in the map, reusing its cached hash.
+    fn insert_internal(&mut self, hash: u64, key: K, value: V) {
+        // let _guard = flame::start_guard("insert");
+        let ix = self.cache.find_free(self.ix(hash));
+
+        // Got free spot!
+        if let Slot::Deleted = self.cache.slot(ix) {
+            self.deleted -= 1;
+        }
+
+        self.count += 1;
+        self.cache.put(ix, hash, key, value);
+    }
+
+    #[inline]
+    fn ix(&self, hash: u64) -> usize {
+        (hash & self.mod_mask) as usize
+    }
+
+
+    #[inline]
+    fn lim(&self) -> usize {
+        2u64.pow(self.size) as usize
+    }
+
+
+    /// Load limit in entries for a table of `lim` buckets.
+    #[inline]
+    fn max_load(&self, lim: usize) -> usize {
+        self.config.max_load(lim)
+    }
+
+    /// Smallest table size that holds `entries` under the load limit.
+    fn size_for(&self, entries: usize) -> Result<u32, TryReserveError> {
+        let mut size = 1;
+
+        while self.max_load(1 << size) < entries {
+            size += 1;
+
+            if size >= usize::BITS {
+                return Err(TryReserveError::CapacityOverflow);
+            }
+        }
+
+        Ok(size)
+    }
+
+    /// Rebuilds the table with `2^size` buckets.
+    fn resize(&mut self, size: u32) {
+        if let Err(err) = self.try_resize(size) {
+            err.bail();
+        }
+    }
+
+    /// Allocates an empty table of `2^size` buckets and swaps it in, returning the previous one.
+    ///
+    /// If the allocation fails the map is left untouched.
+    fn try_swap_cache(&mut self, size: u32) -> Result<T, TryReserveError> {
+        let lim = 1usize.checked_shl(size).ok_or(TryReserveError::CapacityOverflow)?;
+        let cache = T::try_with_len(lim)?;
+
+        self.size = size;
+        self.mod_mask = (lim as u64) - 1;
+        self.deleted = 0;
+
+        self.grow_at = self.max_load(lim);
+        self.headroom = 0;
+
+        Ok(mem::replace(&mut self.cache, cache))
+    }
+
+    /// Rehashes every entry into a fresh table of `2^size` buckets, dropping all tombstones.
+    ///
+    /// Also finishes a running incremental resize. If the allocation fails the map is left untouched.
+    fn try_resize(&mut self, size: u32) -> Result<(), TryReserveError> {
+        let vec = self.try_swap_cache(size)?;
+        let old = mem::replace(&mut self.old, T::new());
+        self.migrated = 0;
+
+        let old_count = self.count;
+        self.count = 0;
+
+        for (h, k, v) in vec.into_entries().chain(old.into_entries()) {
+            self.insert_internal(h, k, v);
+        }
+
+        debug_assert!(self.cache.len() == self.lim(), "cache vector the wrong length, lim: {:?} cache: {:?}", self.lim(), self.cache.len());
+        debug_assert_eq!(old_count, self.count, "Different count after increase cache! Old: {}, New: {}", old_count, self.count);
+
+        Ok(())
+    }
+
+    /// Makes room for the next insert, following the configured check policy.
+    #[inline]
+    fn check_load(&mut self) {
+        if let Err(err) = self.try_check_load() {
+            err.bail();
+        }
+    }
+
+    /// Makes room for the next insert, following the configured check policy.
+    ///
+    /// If the table can't grow an error is returned and the map is left as it was.
+    fn try_check_load(&mut self) -> Result<(), TryReserveError> {
+        match self.config.check_policy {
+            CheckPolicy::EveryInsert => self.try_ensure_load_rate(1),
+            CheckPolicy::Amortized(n) => {
+                // Anything that rebuilds the table zeroes the headroom, forcing a new check.
+                if self.headroom == 0 {
+                    let n = n.min(self.grow_at.max(1));
+                    self.try_ensure_load_rate(n)?;
+                    self.headroom = n;
+                }
+
+                self.headroom -= 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Makes room for `additional` new entries without passing the load limit.
+    ///
+    /// Tombstones lengthen probes just like live entries, so both count towards the load.
+    /// When a good part of the load is tombstones the table is rehashed in place instead of grown.
+    fn try_ensure_load_rate(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        // let _guard2 = flame::start_guard("ensure_load_rate");
+        let live = self.count.checked_add(additional).ok_or(TryReserveError::CapacityOverflow)?;
+
+        if live.saturating_add(self.deleted) <= self.grow_at {
+            return Ok(());
+        }
+
+        // Only one incremental resize at a time, finish the running one first.
+        self.migrate(usize::MAX);
+
+        if self.deleted > 0 && live.saturating_mul(4) <= self.grow_at * 3 {
+            let size = self.size;
+            self.try_resize(size)
+        } else {
+            let size = self.size_for(live)?.max(self.size + self.config.growth_shift);
+
+            if self.config.resize_step > 0 {
+                self.old = self.try_swap_cache(size)?;
+                Ok(())
+            } else {
+                self.try_resize(size)
+            }
+        }
+    }
+
+    /// Moves the next old buckets over while resizing incrementally.
+    #[inline]
+    fn migrate_step(&mut self) {
+        if !self.old.is_empty() {
+            // Inserts left before the next load check fails and has to finish moving in one go.
+            let ahead = match self.config.check_policy {
+                CheckPolicy::EveryInsert => 1,
+                CheckPolicy::Amortized(n) => n.min(self.grow_at.max(1)),
+            };
+            let inserts = self.grow_at.saturating_sub(self.count + self.deleted + ahead);
+
+            // Keep up with the inserts, never stepping below the configured pace.
+            let left = self.old.len() - self.migrated;
+            let step = self.config.resize_step.max(left.div_ceil(inserts + 1));
+            self.migrate(step);
+        }
+    }
+
+    /// Moves up to `step` old buckets into the table, freeing the old ones once all are moved.
+    fn migrate(&mut self, step: usize) {
+        let end = self.migrated.saturating_add(step).min(self.old.len());
+
+        while self.migrated < end {
+            if let Slot::Full(_) = self.old.slot(self.migrated) {
+                let (h, k, v) = self.take_old(self.migrated);
+                self.insert_internal(h, k, v);
+            }
+
+            self.migrated += 1;
+        }
+
+        if self.migrated == self.old.len() {
+            self.old = T::new();
+            self.migrated = 0;
+        }
+    }
+
+    /// Halves the table if the load rate fell below the configured min load factor.
+    fn ensure_min_load(&mut self) {
+        if self.size > 1 && (self.count as f64) < self.cache.len() as f64 * self.config.min_load_factor {
+            let size = self.size - 1;
+            self.resize(size);
+        }
+    }
+}
+
+
+
+
+impl<K, V, S, T> Extend<(K, V)> for FastMap<K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher, T: Storage<K, V> {
+    /// Inserts all pairs, reserving room for the iterator's lower size bound first.
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+
+        // Keys already in the map don't take new buckets, so only count on half of them being new.
+        let hint = iter.size_hint().0;
+        self.reserve(if self.is_empty() { hint } else { hint.div_ceil(2) });
+
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+
+impl<'a, K, V, S, T> Extend<(&'a K, &'a V)> for FastMap<K, V, S, T>
+    where K: Eq + Hash + Copy, V: Copy, S: BuildHasher, T: Storage<K, V> {
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|(&k, &v)| (k, v)));
+    }
+}
+
+impl<K, V, S, T> FromIterator<(K, V)> for FastMap<K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher + Default, T: Storage<K, V> {
+    /// Builds a map from key/value pairs, later pairs replacing earlier ones with the same key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let map: FastMap<u64, u64> = (0..10).map(|i| (i, i * 2)).collect();
+    /// assert_eq!(map[&4], 8);
+    /// ```
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = FastMap::with_hasher(S::default());
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, S, T, const N: usize> From<[(K, V); N]> for FastMap<K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher + Default, T: Storage<K, V> {
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let map: FastMap<u64, &str> = FastMap::from([(21, "Eat my shorts"), (42, "Don't have a cow")]);
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    fn from(arr: [(K, V); N]) -> Self {
+        IntoIterator::into_iter(arr).collect()
+    }
+}
+
+impl<K, V, S, T> Default for FastMap<K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher + Default, T: Storage<K, V> {
+    fn default() -> Self {
+        FastMap::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S, T> Clone for FastMap<K, V, S, T>
+    where K: Eq + Hash + Clone, V: Clone, S: Clone, T: Storage<K, V> + Clone {
+    /// Copies the bucket layout as it is, nothing is rehashed.
+    fn clone(&self) -> Self {
+        FastMap {
+            cache: self.cache.clone(),
+            old: self.old.clone(),
+            migrated: self.migrated,
+            size: self.size,
+            mod_mask: self.mod_mask,
+            count: self.count,
+            deleted: self.deleted,
+            grow_at: self.grow_at,
+            headroom: self.headroom,
+            config: self.config,
+            hasher: self.hasher.clone(),
+            entries: PhantomData,
+        }
+    }
+}
+
+impl<K, V, S, T> fmt::Debug for FastMap<K, V, S, T>
+    where K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher, T: Storage<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S, T> PartialEq for FastMap<K, V, S, T>
+    where K: Eq + Hash, V: PartialEq, S: BuildHasher, T: Storage<K, V> {
+    /// Maps are equal when they hold the same entries, wherever those sit in the table.
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<K, V, S, T> Eq for FastMap<K, V, S, T>
+    where K: Eq + Hash, V: Eq, S: BuildHasher, T: Storage<K, V> {}
+
+impl<K, Q, V, S, T> Index<&Q> for FastMap<K, V, S, T>
+    where K: Eq + Hash + Borrow<Q>, Q: Eq + Hash + ?Sized, S: BuildHasher, T: Storage<K, V> {
+    type Output = V;
+
+    /// Returns the value for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not in the map.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in FastMap")
+    }
+}
+
+
+impl<K, V, S, T> IntoIterator for FastMap<K, V, S, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, T>;
+
+    /// Consumes the map, yielding its entries in bucket order.
+    fn into_iter(self) -> IntoIter<K, V, T> {
+        IntoIter {
+            outer: self.cache.into_entries().chain(self.old.into_entries()),
+            remaining: self.count,
+        }
+    }
+}
+
+impl<'a, K, V, S, T> IntoIterator for &'a FastMap<K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher, T: Storage<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, T>;
+
+    fn into_iter(self) -> Iter<'a, K, V, T> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S, T> IntoIterator for &'a mut FastMap<K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher, T: Storage<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V, T>;
+
+    fn into_iter(self) -> IterMut<'a, K, V, T> {
+        self.iter_mut()
+    }
+}
 
 
-    fn rebuild_cache(&mut self) {
-        let old_count = self.count;
-        self.count = 0;
+use std::iter::{Chain, FusedIterator};
 
-        let mut vec: Vec<Bucket<K, V>> = Vec::new();
+// Every iterator counts down the live entries it has left, so it knows its exact length
+// and can stop without walking the empty buckets at the end of the table.
 
-        vec.append(&mut self.cache);
+// // ***************** Iter *********************
 
-        for _ in 0..self.lim() + 10 {
-            self.cache.push(Bucket::Empty);
-        }
+pub struct Iter<'a, K: 'a, V: 'a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    // The table, then the buckets still to move while resizing incrementally.
+    outer: Chain<T::Iter<'a>, T::Iter<'a>>,
+    remaining: usize,
+}
 
-        while let Some(item) = vec.pop() {
+impl<'a, K, V, T> Iterator for Iter<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    type Item = (&'a K, &'a V);
 
-            match item {
-                Bucket::Value(_, k, v) => {
-                    self.insert_internal(k, v);
-                    // let ix = self.ix(h);
-                    // self.cache[ix] = Bucket::Value(h, k, v);
-                }
-                _ => (),
-            }
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.remaining == 0 {
+            return None;
         }
 
-         // debug_assert!(self.cache.len() == self.lim(), "cache vector the wrong length, lim: {:?} cache: {:?}", self.lim(), self.cache.len());
-        debug_assert_eq!(old_count, self.count, "Different count after increase cache! Old: {}, New: {}", old_count, self.count);
+        self.remaining -= 1;
+        self.outer.next()
     }
 
-    fn ensure_load_rate(&mut self) {
-        // let _guard2 = flame::start_guard("ensure_load_rate");
-        while ((self.count*100) / self.cache.len()) > 70 {
-            self.increase_cache();
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V, T> DoubleEndedIterator for Iter<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.remaining == 0 {
+            return None;
         }
+
+        self.remaining -= 1;
+        self.outer.next_back()
     }
+}
 
+impl<'a, K, V, T> ExactSizeIterator for Iter<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
 
-    /// Number of elements in map.
-    ///
-    pub fn len(&self) -> usize {
-        self.count as usize
+impl<'a, K, V, T> FusedIterator for Iter<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
+
+impl<'a, K, V, T> Clone for Iter<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    fn clone(&self) -> Self {
+        Iter { outer: self.outer.clone(), remaining: self.remaining }
     }
+}
 
 
-    /// Force count number of slots filled.
-    ///
-    pub fn load(&self) -> u64 {
-        let mut count = 0;
+// ***************** Iter Mut *********************
 
-        for item in self.cache.iter() {
-            if let Bucket::Value(_, _, _) = *item {
-                count += 1;
-            }
-        }
+pub struct IterMut<'a, K: 'a, V: 'a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    outer: Chain<T::IterMut<'a>, T::IterMut<'a>>,
+    remaining: usize,
+}
 
-        count
-    }
+impl<'a, K, V, T> Iterator for IterMut<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    type Item = (&'a K, &'a mut V);
 
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        if self.remaining == 0 {
+            return None;
+        }
 
+        self.remaining -= 1;
+        self.outer.next()
+    }
 
-    pub fn load_rate(&self) -> f64 {
-        (self.count as f64) / (self.cache.len() as f64) * 100f64
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
+}
 
+impl<'a, K, V, T> DoubleEndedIterator for IterMut<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        if self.remaining == 0 {
+            return None;
+        }
 
-    /// Total number of slots available.
-    ///
-    pub fn capacity(&self) -> usize {
-        self.cache.len()
+        self.remaining -= 1;
+        self.outer.next_back()
     }
+}
 
+impl<'a, K, V, T> ExactSizeIterator for IterMut<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
 
-    pub fn assert_count(&self) -> bool {
-        let mut count = 0;
+impl<'a, K, V, T> FusedIterator for IterMut<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
 
-        for item in self.cache.iter() {
-            match *item {
-                Bucket::Value(_, _, _) => count += 1,
-                _ => ()
-            }
-        }
 
-        self.count == count
-    }
+// ***************** Into Iter *********************
 
+pub struct IntoIter<K, V, T = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    outer: Chain<T::IntoEntries, T::IntoEntries>,
+    remaining: usize,
+}
 
-    pub fn collisions(&self) -> FastMap<u64, u64> {
-        let map = FastMap::new();
+impl<K, V, T> Iterator for IntoIter<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    type Item = (K, V);
 
-        // for s in self.cache.iter() {
-        //     let key = s.len() as u64;
-        //     if key > 0 {
-        //         if !map.contains_key(key) {
-        //             map.insert(key, 1);
-        //         } else {
-        //             let counter = map.get_mut(key).unwrap();
-        //             *counter += 1;
-        //         }
-        //     }
-        // }
+    #[inline]
+    fn next(&mut self) -> Option<(K, V)> {
+        if self.remaining == 0 {
+            return None;
+        }
 
-        // map.sort();
+        self.remaining -= 1;
+        self.outer.next().map(|(_, k, v)| (k, v))
+    }
 
-        map
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
+impl<K, V, T> DoubleEndedIterator for IntoIter<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(K, V)> {
+        if self.remaining == 0 {
+            return None;
+        }
 
+        self.remaining -= 1;
+        self.outer.next_back().map(|(_, k, v)| (k, v))
+    }
+}
 
+impl<K, V, T> ExactSizeIterator for IntoIter<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
 
+impl<K, V, T> FusedIterator for IntoIter<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
 
-use std::slice::Iter as SliceIter;
-use std::slice::IterMut as SliceIterMut;
 
-// // ***************** Iter *********************
+// ***************** Drain *********************
 
-pub struct Iter<'a, K: 'a, V: 'a>
-    where K: Eq + Hash {
-    outer: SliceIter<'a, Bucket<K, V>>,
+pub struct Drain<'a, K: 'a, V: 'a, S: 'a = BuildMurmur2_64a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, S: BuildHasher, T: Storage<K, V> {
+    map: &'a mut FastMap<K, V, S, T>,
+    ix: usize,
 }
 
-impl<'a, K, V> Iterator for Iter<'a, K, V>
-    where K: Eq + Hash{
-    type Item = (&'a K, &'a V);
+impl<'a, K, V, S, T> Iterator for Drain<'a, K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher, T: Storage<K, V> {
+    type Item = (K, V);
 
     #[inline]
-    fn next(&mut self) -> Option<(&'a K, &'a V)> {
-        loop {
-            match self.outer.next() {
-                Some(r) => {
-                    match *r {
-                        Bucket::Value(_, ref k, ref v) => return Some((&k, &v)),
-                        _ => (),
-                    }
-                },
-                None => return None,
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.ix < self.map.buckets_len() {
+            let ix = self.ix;
+            self.ix += 1;
+
+            if self.map.bucket_mut(ix).is_some() {
+                return Some(self.map.take_bucket(ix));
             }
         }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.map.count, Some(self.map.count))
     }
 }
 
+impl<'a, K, V, S, T> ExactSizeIterator for Drain<'a, K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher, T: Storage<K, V> {}
 
-// ***************** Iter Mut *********************
+impl<'a, K, V, S, T> FusedIterator for Drain<'a, K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher, T: Storage<K, V> {}
 
-pub struct IterMut<'a, K: 'a, V: 'a>
-    where K: Eq + Hash {
-    outer: SliceIterMut<'a, Bucket<K, V>>,
+impl<'a, K, V, S, T> Drop for Drain<'a, K, V, S, T>
+    where K: Eq + Hash, S: BuildHasher, T: Storage<K, V> {
+    /// Drops what wasn't drained and wipes the tombstones left behind.
+    fn drop(&mut self) {
+        self.map.clear();
+    }
 }
 
-impl<'a, K, V> Iterator for IterMut<'a, K, V>
-    where K: Eq + Hash{
-    type Item = (&'a K, &'a mut V);
+
+// ***************** Extract If *********************
+
+pub struct ExtractIf<'a, K: 'a, V: 'a, F, S: 'a = BuildMurmur2_64a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V>, F: FnMut(&K, &mut V) -> bool {
+    map: &'a mut FastMap<K, V, S, T>,
+    ix: usize,
+    pred: F,
+}
+
+impl<'a, K, V, F, S, T> Iterator for ExtractIf<'a, K, V, F, S, T>
+    where K: Eq + Hash, T: Storage<K, V>, F: FnMut(&K, &mut V) -> bool {
+    type Item = (K, V);
 
     #[inline]
-    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
-         loop {
-            match self.outer.next() {
-                Some(r) => {
-                    match *r {
-                        Bucket::Value(_, ref k, ref mut v) => return Some((&k, &mut *v)),
-                        _ => (),
-                    }
-                },
-                None => return None,
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.ix < self.map.buckets_len() {
+            let ix = self.ix;
+            self.ix += 1;
+
+            if let Some((k, v)) = self.map.bucket_mut(ix) {
+                if (self.pred)(k, v) {
+                    return Some(self.map.take_bucket(ix));
+                }
             }
         }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.map.count))
+    }
+}
+
+impl<'a, K, V, F, S, T> FusedIterator for ExtractIf<'a, K, V, F, S, T>
+    where K: Eq + Hash, T: Storage<K, V>, F: FnMut(&K, &mut V) -> bool {}
+
+impl<'a, K, V, F, S, T> Drop for ExtractIf<'a, K, V, F, S, T>
+    where K: Eq + Hash, T: Storage<K, V>, F: FnMut(&K, &mut V) -> bool {
+    /// Shrinks the table now that the walk is over, if the min load factor asks for it.
+    fn drop(&mut self) {
+        self.map.ensure_min_load();
     }
 }
 
 
 // ***************** Values Iter *********************
 
-pub struct Values<'a, K:'a, V: 'a>
-    where K: Eq + Hash{
-    inner: Iter<'a, K, V>
+pub struct Values<'a, K: 'a, V: 'a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    inner: Iter<'a, K, V, T>
 }
 
-
-impl<'a, K, V> Iterator for Values<'a, K, V>
-     where K: Eq + Hash{
+impl<'a, K, V, T> Iterator for Values<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
     type Item = &'a V;
 
-    #[inline] fn next(&mut self) -> Option<(&'a V)> { self.inner.next().map(|kv| kv.1) }
+    #[inline] fn next(&mut self) -> Option<&'a V> { self.inner.next().map(|kv| kv.1) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<'a, K, V, T> DoubleEndedIterator for Values<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    #[inline] fn next_back(&mut self) -> Option<&'a V> { self.inner.next_back().map(|kv| kv.1) }
+}
+
+impl<'a, K, V, T> ExactSizeIterator for Values<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
+
+impl<'a, K, V, T> FusedIterator for Values<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
+
+impl<'a, K, V, T> Clone for Values<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    fn clone(&self) -> Self {
+        Values { inner: self.inner.clone() }
+    }
+}
+
+
+// ***************** Values Mut *********************
+
+pub struct ValuesMut<'a, K: 'a, V: 'a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    inner: IterMut<'a, K, V, T>
+}
+
+impl<'a, K, V, T> Iterator for ValuesMut<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    type Item = &'a mut V;
+
+    #[inline] fn next(&mut self) -> Option<&'a mut V> { self.inner.next().map(|kv| kv.1) }
     #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
 }
 
+impl<'a, K, V, T> DoubleEndedIterator for ValuesMut<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    #[inline] fn next_back(&mut self) -> Option<&'a mut V> { self.inner.next_back().map(|kv| kv.1) }
+}
+
+impl<'a, K, V, T> ExactSizeIterator for ValuesMut<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
+
+impl<'a, K, V, T> FusedIterator for ValuesMut<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
+
+
 // ***************** Keys Iter *********************
 
-pub struct Keys<'a, K: 'a, V: 'a>
-    where K: Eq + Hash {
-    inner: Iter<'a, K, V>
+pub struct Keys<'a, K: 'a, V: 'a, T: 'a = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    inner: Iter<'a, K, V, T>
 }
 
-impl<'a, K, V> Iterator for Keys<'a, K, V>
-    where K: Eq + Hash{
+impl<'a, K, V, T> Iterator for Keys<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
     type Item = &'a K;
 
     #[inline] fn next(&mut self) -> Option<&'a K> { self.inner.next().map(|kv| kv.0) }
     #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
 }
 
-// // ***************** Values Mut *********************
+impl<'a, K, V, T> DoubleEndedIterator for Keys<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    #[inline] fn next_back(&mut self) -> Option<&'a K> { self.inner.next_back().map(|kv| kv.0) }
+}
+
+impl<'a, K, V, T> ExactSizeIterator for Keys<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
+
+impl<'a, K, V, T> FusedIterator for Keys<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
+
+impl<'a, K, V, T> Clone for Keys<'a, K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    fn clone(&self) -> Self {
+        Keys { inner: self.inner.clone() }
+    }
+}
+
+
+// ***************** Into Keys *********************
+
+pub struct IntoKeys<K, V, T = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    inner: IntoIter<K, V, T>
+}
+
+impl<K, V, T> Iterator for IntoKeys<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    type Item = K;
+
+    #[inline] fn next(&mut self) -> Option<K> { self.inner.next().map(|kv| kv.0) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<K, V, T> DoubleEndedIterator for IntoKeys<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    #[inline] fn next_back(&mut self) -> Option<K> { self.inner.next_back().map(|kv| kv.0) }
+}
 
-// pub struct ValuesMut<'a, V: 'a> {
-//     inner: Iter<'a, V>
-// }
+impl<K, V, T> ExactSizeIterator for IntoKeys<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
 
+impl<K, V, T> FusedIterator for IntoKeys<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
 
-// impl<'a, V> Iterator for ValuesMut<'a, V> {
-//     type Item = &'a V;
 
-//     #[inline] fn next(&mut self) -> Option<(&'a V)> { self.inner.next().map(|kv| kv.1) }
-//     #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
-// }
+// ***************** Into Values *********************
+
+pub struct IntoValues<K, V, T = DefaultStorage<K, V>>
+    where K: Eq + Hash, T: Storage<K, V> {
+    inner: IntoIter<K, V, T>
+}
+
+impl<K, V, T> Iterator for IntoValues<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    type Item = V;
+
+    #[inline] fn next(&mut self) -> Option<V> { self.inner.next().map(|kv| kv.1) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<K, V, T> DoubleEndedIterator for IntoValues<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {
+    #[inline] fn next_back(&mut self) -> Option<V> { self.inner.next_back().map(|kv| kv.1) }
+}
+
+impl<K, V, T> ExactSizeIterator for IntoValues<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
+
+impl<K, V, T> FusedIterator for IntoValues<K, V, T>
+    where K: Eq + Hash, T: Storage<K, V> {}
 
 
 // use std::hash::{Hasher, BuildHasher};
 
 // ***** Murmur2_64a *****
 
+// Input up to this many bytes is buffered and hashed by `murmur_hash64a` in `finish`.
+const MURMUR_INLINE: usize = 32;
+
+/// Murmur2_64a hasher, build one per key through `BuildMurmur2_64a`.
+///
+/// Up to 32 bytes from all `write` calls are buffered, and `finish` returns
+/// `murmur_hash64a` of them. MurmurHash64A mixes the total length in before the first
+/// block, which a hasher can't know up front, so longer input is hashed block by block as
+/// it is written and the length is mixed in by `finish` instead. It never allocates.
+///
+/// Past 32 bytes the hash therefore differs from `murmur_hash64a` of the same bytes,
+/// whether they come in one `write` or many. It only ever depends on the concatenated
+/// bytes though, however a `Hash` impl splits its writes.
 #[allow(non_camel_case_types)]
+#[derive(Clone, Debug)]
 pub struct Murmur2_64a {
-    seed: u64
+    seed: u64,
+    len: usize,
+    // Running state once past the inline buffer.
+    hash: u64,
+    inline: [u8; MURMUR_INLINE],
+    // The bytes of a block not yet complete, `len % 8` of them.
+    carry: [u8; 8],
 }
 
 impl Murmur2_64a {
     pub fn new() -> Murmur2_64a {
-        Murmur2_64a{ seed: 0 }
+        Murmur2_64a::with_seed(0)
+    }
+
+    /// Creates a Murmur2_64a that mixes `seed` into the hash.
+    pub fn with_seed(seed: u64) -> Murmur2_64a {
+        Murmur2_64a {
+            seed,
+            len: 0,
+            hash: seed,
+            inline: [0; MURMUR_INLINE],
+            carry: [0; 8],
+        }
+    }
+
+    #[inline]
+    fn write_fixed<const N: usize>(&mut self, bytes: [u8; N]) {
+        let end = self.len + N;
+
+        if end <= MURMUR_INLINE {
+            self.inline[self.len..end].copy_from_slice(&bytes);
+            self.len = end;
+        } else {
+            self.write(&bytes);
+        }
+    }
+
+    /// Mixes `msg` into the running state, completing the carried block first.
+    fn stream(&mut self, mut msg: &[u8]) {
+        let filled = self.len % 8;
+
+        if filled > 0 {
+            let take = (8 - filled).min(msg.len());
+            self.carry[filled..filled + take].copy_from_slice(&msg[..take]);
+            self.len += take;
+            msg = &msg[take..];
+
+            if filled + take < 8 {
+                return;
+            }
+
+            self.hash = murmur_block(self.hash, u64::from_le_bytes(self.carry));
+        }
+
+        let blocks = msg.chunks_exact(8);
+        let rest = blocks.remainder();
+
+        for block in blocks {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(block);
+            self.hash = murmur_block(self.hash, u64::from_le_bytes(buf));
+        }
+
+        self.carry[..rest.len()].copy_from_slice(rest);
+        self.len += msg.len();
+    }
+}
+
+impl Default for Murmur2_64a {
+    fn default() -> Murmur2_64a {
+        Murmur2_64a::new()
     }
 }
 
@@ -596,107 +1899,199 @@ impl Murmur2_64a {
 impl Hasher for Murmur2_64a {
     #[inline]
     fn write(&mut self, msg: &[u8]) {
-        self.seed = murmur_hash64a(msg, self.seed);
+        let end = self.len + msg.len();
+
+        if end <= MURMUR_INLINE {
+            self.inline[self.len..end].copy_from_slice(msg);
+            self.len = end;
+            return;
+        }
+
+        if self.len <= MURMUR_INLINE {
+            // Outgrew the buffer, start streaming from its first byte.
+            let buffered = self.inline;
+            let len = mem::replace(&mut self.len, 0);
+            self.stream(&buffered[..len]);
+        }
+
+        self.stream(msg);
+    }
+
+    // Integers are written little-endian so hashes match across platforms.
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.write_fixed([i]);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write_fixed(i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write_fixed(i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.write_fixed(i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
     }
 
     #[inline]
     fn finish(&self) -> u64 {
-        self.seed as u64
+        if self.len <= MURMUR_INLINE {
+            return murmur_hash64a(&self.inline[..self.len], self.seed);
+        }
+
+        let hash = murmur_tail(self.hash, &self.carry[..self.len % 8]);
+        let hash = (hash ^ (self.len as u64).wrapping_mul(MURMUR_M)).wrapping_mul(MURMUR_M);
+        murmur_avalanche(hash)
+    }
+}
+
+
+// ***** BuildMurmur2_64a *****
+
+/// Builds fresh Murmur2_64a hashers, the default hash builder for FastMap.
+///
+/// Only holds the seed, so it also works as the `S` of `std::collections::HashMap`.
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildMurmur2_64a {
+    seed: u64
+}
+
+impl BuildMurmur2_64a {
+    pub fn new() -> BuildMurmur2_64a {
+        BuildMurmur2_64a::with_seed(0)
+    }
+
+    /// Creates a BuildMurmur2_64a whose hashers mix `seed` into every hash.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::{BuildMurmur2_64a, FastMap};
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::with_hasher(BuildMurmur2_64a::with_seed(42));
+    /// map.insert(21, 42);
+    /// ```
+    pub fn with_seed(seed: u64) -> BuildMurmur2_64a {
+        BuildMurmur2_64a{ seed }
+    }
+
+    /// The seed handed to every Murmur2_64a this builds.
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
 }
 
-impl BuildHasher for Murmur2_64a {
+impl BuildHasher for BuildMurmur2_64a {
     type Hasher = Murmur2_64a;
     fn build_hasher(&self) -> Self::Hasher {
-        let mut murm = Murmur2_64a::new();
-        murm.seed = self.seed;
-        murm
+        Murmur2_64a::with_seed(self.seed)
     }
 }
 
+/// MurmurHash64A of `key`.
+///
+/// Blocks are read little-endian, matching the reference implementation on
+/// little-endian targets and giving the same output on big-endian ones.
 pub fn murmur_hash64a(key: &[u8], seed: u64) -> u64 {
-    let m : u64 = 0xc6a4a7935bd1e995;
-    let r : u8 = 47;
-
     let len = key.len();
-    let mut hash : u64 = seed ^ ((len as u64).wrapping_mul(m));
+    let mut hash : u64 = seed ^ ((len as u64).wrapping_mul(MURMUR_M));
 
-    // let end = len >> 3;
-    let nblocks = (len >> 3) as isize;
+    let blocks = key.chunks_exact(8);
+    let tail = blocks.remainder();
 
-    let mut k: u64;
-    let blocks = key.as_ptr() as *mut u64;
+    for block in blocks {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(block);
+        hash = murmur_block(hash, u64::from_le_bytes(buf));
+    }
 
-    unsafe {
-        for i in 0..nblocks {
-            k = *blocks.offset(i);
+    murmur_avalanche(murmur_tail(hash, tail))
+}
 
-            k = k.wrapping_mul(m);
-            k ^= k >> r;
-            k = k.wrapping_mul(m);
+const MURMUR_M: u64 = 0xc6a4a7935bd1e995;
+const MURMUR_R: u8 = 47;
 
-            hash ^= k;
-            hash = hash.wrapping_mul(m);
-        }
+#[inline]
+fn murmur_block(hash: u64, mut k: u64) -> u64 {
+    k = k.wrapping_mul(MURMUR_M);
+    k ^= k >> MURMUR_R;
+    k = k.wrapping_mul(MURMUR_M);
 
-        let tail = blocks.offset(nblocks) as *mut u8;
+    (hash ^ k).wrapping_mul(MURMUR_M)
+}
 
-        match len & 7 {
-            7 => {
-                hash ^= (*tail.offset(6) as u64) << 48;
-                hash ^= (*tail.offset(5) as u64) << 40;
-                hash ^= (*tail.offset(4) as u64) << 32;
-                hash ^= (*tail.offset(3) as u64) << 24;
-                hash ^= (*tail.offset(2) as u64) << 16;
-                hash ^= (*tail.offset(1) as u64) << 8;
-                hash ^= *tail as u64;
-                hash = hash.wrapping_mul(m);
-            },
-            6 => {
-                hash ^= (*tail.offset(5) as u64) << 40;
-                hash ^= (*tail.offset(4) as u64) << 32;
-                hash ^= (*tail.offset(3) as u64) << 24;
-                hash ^= (*tail.offset(2) as u64) << 16;
-                hash ^= (*tail.offset(1) as u64) << 8;
-                hash ^= *tail as u64;
-                hash = hash.wrapping_mul(m);
-            },
-            5 => {
-                hash ^= (*tail.offset(4) as u64) << 32;
-                hash ^= (*tail.offset(3) as u64) << 24;
-                hash ^= (*tail.offset(2) as u64) << 16;
-                hash ^= (*tail.offset(1) as u64) << 8;
-                hash ^= *tail as u64;
-                hash = hash.wrapping_mul(m);
-            },
-            4 => {
-                hash ^= (*tail.offset(3) as u64) << 24;
-                hash ^= (*tail.offset(2) as u64) << 16;
-                hash ^= (*tail.offset(1) as u64) << 8;
-                hash ^= *tail as u64;
-                hash = hash.wrapping_mul(m);
-            },
-            3 => {
-                hash ^= (*tail.offset(2) as u64) << 16;
-                hash ^= (*tail.offset(1) as u64) << 8;
-                hash ^= *tail as u64;
-                hash = hash.wrapping_mul(m);
-            },
-            2 => {
-                hash ^= (*tail.offset(1) as u64) << 8;
-                hash ^= *tail as u64;
-                hash = hash.wrapping_mul(m);
-            },
-            1 => {
-                hash ^= *tail as u64;
-                hash = hash.wrapping_mul(m);
-            },
-            _ => {},
+#[inline]
+fn murmur_tail(mut hash: u64, tail: &[u8]) -> u64 {
+    if !tail.is_empty() {
+        for (i, b) in tail.iter().enumerate() {
+            hash ^= (*b as u64) << (8 * i);
         }
+        hash = hash.wrapping_mul(MURMUR_M);
     }
 
-    hash ^= hash >> r;
-    hash = hash.wrapping_mul(m);
-    hash ^= hash >> r;
     hash
 }
+
+#[inline]
+fn murmur_avalanche(mut hash: u64) -> u64 {
+    hash ^= hash >> MURMUR_R;
+    hash = hash.wrapping_mul(MURMUR_M);
+    hash ^ (hash >> MURMUR_R)
+}
+
+
+// ***** RandomMurmur *****
+
+/// Builds Murmur2_64a hashers with a seed drawn from OS entropy when created.
+///
+/// Every RandomMurmur gets its own seed, so two maps place the same keys differently.
+#[cfg(feature = "random")]
+#[derive(Clone, Copy, Debug)]
+pub struct RandomMurmur {
+    seed: u64
+}
+
+#[cfg(feature = "random")]
+impl RandomMurmur {
+    /// Creates a RandomMurmur with a fresh seed.
+    ///
+    /// Panics if the OS entropy source is unavailable.
+    pub fn new() -> RandomMurmur {
+        let mut buf = [0u8; 8];
+        getrandom::getrandom(&mut buf).expect("Failed to read seed from OS entropy!");
+
+        RandomMurmur { seed: u64::from_le_bytes(buf) }
+    }
+
+    /// The seed handed to every Murmur2_64a this builds.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+#[cfg(feature = "random")]
+impl Default for RandomMurmur {
+    fn default() -> RandomMurmur {
+        RandomMurmur::new()
+    }
+}
+
+#[cfg(feature = "random")]
+impl BuildHasher for RandomMurmur {
+    type Hasher = Murmur2_64a;
+    fn build_hasher(&self) -> Self::Hasher {
+        Murmur2_64a::with_seed(self.seed)
+    }
+}
@@ -0,0 +1,1504 @@
+//! A Robin Hood hashing variant of `FastMap`.
+//!
+//! Entries keep their probe distance next to the cached hash. On insert an entry that is
+//! further from its home bucket takes the place of one that is closer, which keeps probe
+//! lengths even. A lookup can stop as soon as it passes an entry closer to home than it
+//! would be, so misses are cheap. Removes shift the following entries back instead of
+//! leaving tombstones.
+
+use std::borrow::Borrow;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::{FromIterator, FusedIterator};
+use std::mem;
+use std::ops::Index;
+use std::slice::Iter as SliceIter;
+use std::slice::IterMut as SliceIterMut;
+use std::vec::IntoIter as VecIntoIter;
+
+use {BuildMurmur2_64a, CheckPolicy, FastMapConfig, TryReserveError};
+use storage::try_vec;
+
+/// A hash map using Robin Hood linear probing, with the same API as `FastMap`.
+///
+/// It is tuned by the same `FastMapConfig`, except that the table is always rehashed in
+/// one go, so a config with `incremental_resize` is rejected.
+///
+/// # Examples
+///
+/// ```
+/// use fastmap::RobinHoodMap;
+///
+/// let mut map = RobinHoodMap::new();
+/// map.insert(21, "Eat my shorts");
+///
+/// assert_eq!(map.get(&21), Some(&"Eat my shorts"));
+/// assert!(!map.contains_key(&42));
+/// ```
+pub struct RobinHoodMap<K: Eq + Hash, V, S = BuildMurmur2_64a> {
+    cache: Vec<Bucket<K, V>>,
+    size: u32,
+    mod_mask: u64,
+    count: usize,
+    grow_at: usize,
+    headroom: usize,
+    config: FastMapConfig,
+    hasher: S,
+}
+
+#[derive(Clone)]
+enum Bucket<K, V> {
+    // Cached hash, distance from the home bucket, key and value.
+    Value(u64, u32, K, V),
+    Empty,
+}
+
+impl<K, V> RobinHoodMap<K, V>
+    where K: Eq + Hash {
+    /// Creates a new RobinHoodMap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+    /// ```
+    pub fn new() -> Self {
+        RobinHoodMap::with_capacity(4)
+    }
+
+
+    /// Creates a new RobinHoodMap with a at least capacity, all sizes is a power of 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::with_capacity(20);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        RobinHoodMap::with_capacity_and_hasher(capacity, BuildMurmur2_64a::new())
+    }
+
+
+    /// Creates a new RobinHoodMap tuned by `config`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::{FastMapConfig, RobinHoodMap};
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::with_config(FastMapConfig::new().max_load_factor(0.9));
+    /// for i in 0..1000 {
+    ///     map.insert(i, i);
+    /// }
+    /// assert_eq!(map.capacity(), 2048);
+    /// ```
+    pub fn with_config(config: FastMapConfig) -> Self {
+        RobinHoodMap::with_config_and_hasher(config, BuildMurmur2_64a::new())
+    }
+}
+
+impl<K, V, S> RobinHoodMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher {
+    /// Creates a new RobinHoodMap which will use the given hash builder to hash keys.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        RobinHoodMap::with_capacity_and_hasher(4, hash_builder)
+    }
+
+
+    /// Creates a new RobinHoodMap with at least capacity, using the given hash builder to hash keys.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        RobinHoodMap::with_config_and_hasher(FastMapConfig::new().capacity(capacity), hash_builder)
+    }
+
+
+    /// Creates a new RobinHoodMap tuned by `config`, using the given hash builder to hash keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config` asks for incremental resizing, the table is always rehashed
+    /// in one go.
+    pub fn with_config_and_hasher(config: FastMapConfig, hash_builder: S) -> Self {
+        assert!(config.resize_step == 0, "RobinHoodMap doesn't resize incrementally");
+
+        let capacity = config.capacity;
+        let mut map = RobinHoodMap {
+            cache: Vec::new(),
+            size: 0,
+            mod_mask: 0,
+            count: 0,
+            grow_at: 0,
+            headroom: 0,
+            config,
+            hasher: hash_builder,
+        };
+
+        let size = match capacity.checked_next_power_of_two() {
+            Some(lim) => lim.trailing_zeros().max(1),
+            None => TryReserveError::CapacityOverflow.bail(),
+        };
+        map.resize(size);
+
+        map
+    }
+
+    /// Returns a reference to the map's hash builder.
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    /// Returns the configuration the map was created with.
+    pub fn config(&self) -> &FastMapConfig {
+        &self.config
+    }
+
+
+    /// Reserves room for at least `additional` more entries, so they can be inserted
+    /// without the table growing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new table size overflows, and aborts if the allocation fails.
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(err) = self.try_reserve(additional) {
+            err.bail();
+        }
+    }
+
+    /// Tries to reserve room for at least `additional` more entries, so they can be
+    /// inserted without the table growing.
+    ///
+    /// If the size overflows or the allocator fails an error is returned and the map is
+    /// left as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::{RobinHoodMap, TryReserveError};
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+    /// assert_eq!(map.try_reserve(1000), Ok(()));
+    /// assert_eq!(map.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.try_ensure_load_rate(additional)
+    }
+
+
+    /// Insert key/value into the RobinHoodMap.
+    ///
+    /// If the key was already present its value is replaced and the old value returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map = RobinHoodMap::new();
+    /// assert_eq!(map.insert(21, "Eat my shorts"), None);
+    /// assert_eq!(map.insert(21, "Don't have a cow"), Some("Eat my shorts"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.check_load();
+
+        let hash = self.hasher.hash_one(&key);
+
+        match self.probe(hash, &key) {
+            Ok(ix) => Some(mem::replace(self.value_mut(ix), value)),
+            Err((ix, dist)) => {
+                self.insert_at(ix, hash, dist, key, value);
+                None
+            }
+        }
+    }
+
+
+    /// Insert key/value into the map, reporting a failure to grow the table instead
+    /// of aborting.
+    ///
+    /// Like `insert`, but the table is only grown when the key is new, and if that
+    /// growth overflows or the allocator fails the error is returned and the map is left
+    /// as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map = RobinHoodMap::new();
+    /// assert_eq!(map.insert_fallible(21, "Eat my shorts"), Ok(None));
+    /// assert_eq!(map.insert_fallible(21, "Don't have a cow"), Ok(Some("Eat my shorts")));
+    /// ```
+    pub fn insert_fallible(&mut self, key: K, value: V) -> Result<Option<V>, TryReserveError> {
+        let hash = self.hasher.hash_one(&key);
+
+        if let Ok(ix) = self.probe(hash, &key) {
+            return Ok(Some(mem::replace(self.value_mut(ix), value)));
+        }
+
+        // Growing may rehash the table, so look for the key's bucket afterwards.
+        self.try_check_load()?;
+
+        match self.probe(hash, &key) {
+            Ok(_) => panic!("Key showed up while growing the table!"),
+            Err((ix, dist)) => self.insert_at(ix, hash, dist, key, value),
+        }
+
+        Ok(None)
+    }
+
+
+    /// Inserts key/value if the key is not in the map yet, returning a mutable reference
+    /// to the inserted value.
+    ///
+    /// If the key is already present nothing is updated, and an error is returned holding
+    /// the occupied entry and the rejected value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map = RobinHoodMap::new();
+    /// assert_eq!(*map.try_insert(21, "Eat my shorts").unwrap(), "Eat my shorts");
+    ///
+    /// let err = map.try_insert(21, "Don't have a cow").unwrap_err();
+    /// assert_eq!(*err.entry.get(), "Eat my shorts");
+    /// assert_eq!(err.value, "Don't have a cow");
+    /// ```
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<&mut V, OccupiedError<'_, K, V, S>> {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+            Entry::Vacant(entry) => Ok(entry.insert(value)),
+        }
+    }
+
+
+    /// Gets the given key's corresponding entry in the map for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<&str, u64> = RobinHoodMap::new();
+    ///
+    /// for word in "a b a c a b".split(' ') {
+    ///     *map.entry(word).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(map[&"a"], 3);
+    /// assert_eq!(map[&"b"], 2);
+    /// assert_eq!(map[&"c"], 1);
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        // Grow up front so the bucket we find stays valid for a vacant insert.
+        self.check_load();
+
+        let hash = self.hasher.hash_one(&key);
+
+        match self.probe(hash, &key) {
+            Ok(ix) => Entry::Occupied(OccupiedEntry { map: self, ix }),
+            Err((ix, dist)) => Entry::Vacant(VacantEntry { map: self, hash, dist, key, ix }),
+        }
+    }
+
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+    /// map.insert(21, 42);
+    /// assert_eq!(map.get(&21), Some(&42));
+    /// assert_eq!(map.get(&42), None);
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+
+    /// Returns the stored key and a reference to its value.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        match self.cache[self.find(key)?] {
+            Bucket::Value(_, _, ref k, ref v) => Some((k, v)),
+            Bucket::Empty => panic!("get item we found were not there anymore!"),
+        }
+    }
+
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let ix = self.find(key)?;
+        Some(self.value_mut(ix))
+    }
+
+
+    /// Removes a key from the map, returning its value if it was there.
+    ///
+    /// The entries after it are shifted back, no tombstone is left behind.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+    /// map.insert(21, 42);
+    /// assert_eq!(map.remove(&21), Some(42));
+    /// assert_eq!(map.remove(&21), None);
+    /// ```
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+
+    /// Removes a key from the map, returning the stored key and its value if it was there.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let ix = self.find(key)?;
+        let entry = self.remove_at(ix);
+
+        self.ensure_min_load();
+        Some(entry)
+    }
+
+
+    /// Returns true if the key is in the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.find(key).is_some()
+    }
+
+
+    /// Removes all entries, keeping the allocated table.
+    pub fn clear(&mut self) {
+        for i in 0..self.cache.len() {
+            self.cache[i] = Bucket::Empty;
+        }
+
+        self.count = 0;
+    }
+
+    /// Returns true if map is empty
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+
+    //**** Iterators *****
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            outer: self.cache.iter(),
+            remaining: self.count,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            outer: self.cache.iter_mut(),
+            remaining: self.count,
+        }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut { inner: self.iter_mut() }
+    }
+
+    /// Consumes the map, yielding its keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+    /// map.insert(21, 42);
+    /// assert_eq!(map.into_keys().collect::<Vec<_>>(), [21]);
+    /// ```
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys { inner: self.into_iter() }
+    }
+
+    /// Consumes the map, yielding its values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+    /// map.insert(21, 42);
+    /// assert_eq!(map.into_values().collect::<Vec<_>>(), [42]);
+    /// ```
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues { inner: self.into_iter() }
+    }
+
+    /// Removes every entry, yielding them as key/value pairs. The table keeps its size.
+    ///
+    /// Entries the iterator doesn't get to are dropped when it is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+    /// map.insert(21, 42);
+    ///
+    /// assert_eq!(map.drain().collect::<Vec<_>>(), [(21, 42)]);
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> Drain<'_, K, V, S> {
+        Drain { map: self, ix: 0 }
+    }
+
+    /// Keeps only the entries for which `f` returns true, walking the table in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+    /// for i in 0..10 {
+    ///     map.insert(i, i);
+    /// }
+    ///
+    /// map.retain(|k, v| { *v += 1; k % 2 == 0 });
+    /// assert_eq!(map.len(), 5);
+    /// assert_eq!(map.get(&4), Some(&5));
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+        where F: FnMut(&K, &mut V) -> bool {
+        self.extract_if(|k, v| !f(k, v)).for_each(drop);
+    }
+
+    /// Removes and yields the entries for which `pred` returns true, keeping the rest.
+    ///
+    /// Entries are only looked at as the iterator advances, dropping it early keeps
+    /// everything it didn't get to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+    /// for i in 0..10 {
+    ///     map.insert(i, i);
+    /// }
+    ///
+    /// let mut odd: Vec<u64> = map.extract_if(|k, _| k % 2 == 1).map(|(k, _)| k).collect();
+    /// odd.sort();
+    /// assert_eq!(odd, [1, 3, 5, 7, 9]);
+    /// assert_eq!(map.len(), 5);
+    /// ```
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, F, S>
+        where F: FnMut(&K, &mut V) -> bool {
+        // Removing shifts the rest of a run back a bucket. Starting right after an empty
+        // bucket no run wraps past the start, so nothing moves into a bucket already walked.
+        let start = self.cache.iter().position(|b| match *b {
+            Bucket::Empty => true,
+            Bucket::Value(..) => false,
+        }).expect("RobinHoodMap has no empty bucket!");
+
+        let ix = self.next_ix(start);
+        let left = self.cache.len() - 1;
+        ExtractIf { map: self, ix, left, pred }
+    }
+
+
+    //**** Internal hash stuff *****
+
+    /// Finds the bucket holding `key`, stopping early at the first entry closer to home.
+    #[inline]
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.hasher.hash_one(key);
+        self.probe(hash, key).ok()
+    }
+
+
+    /// Number of elements in map.
+    ///
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+
+    pub fn load_rate(&self) -> f64 {
+        (self.count as f64) / (self.cache.len() as f64) * 100f64
+    }
+
+
+    /// Longest probe any lookup takes, the largest distance from home plus one.
+    ///
+    pub fn max_probe_length(&self) -> usize {
+        let mut longest = 0;
+
+        for item in self.cache.iter() {
+            if let Bucket::Value(_, d, _, _) = *item {
+                longest = longest.max(d as usize + 1);
+            }
+        }
+
+        longest
+    }
+
+
+    /// Total number of slots available.
+    ///
+    pub fn capacity(&self) -> usize {
+        self.cache.len()
+    }
+
+
+    /// Shrinks the table as much as possible while keeping every entry under the load limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+    /// for i in 0..1000 {
+    ///     map.insert(i, i);
+    /// }
+    /// for i in 10..1000 {
+    ///     map.remove(&i);
+    /// }
+    ///
+    /// map.shrink_to_fit();
+    /// assert_eq!(map.capacity(), 16);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+
+    /// Shrinks the table as much as possible while it still fits `min_capacity` entries
+    /// under the load limit, and at least the entries already in it. Never grows the table.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let size = match self.size_for(self.count.max(min_capacity)) {
+            Ok(size) => size,
+            Err(_) => return,
+        };
+
+        if size < self.size {
+            self.resize(size);
+        }
+    }
+
+
+    pub fn assert_count(&self) -> bool {
+        let mut count = 0;
+
+        for item in self.cache.iter() {
+            if let Bucket::Value(_, _, _, _) = *item {
+                count += 1;
+            }
+        }
+
+        self.count == count
+    }
+}
+
+
+impl<K, V, S> RobinHoodMap<K, V, S>
+    where K: Eq + Hash {
+    /// Walks the probe sequence of `hash` looking for `key`.
+    ///
+    /// Returns `Ok` with the bucket holding the key, or `Err` with the bucket the key
+    /// belongs in and its distance from home there.
+    #[inline]
+    fn probe<Q>(&self, hash: u64, key: &Q) -> Result<usize, (usize, u32)>
+        where K: Borrow<Q>, Q: Eq + ?Sized {
+        let mut ix = self.ix(hash);
+        let mut dist = 0;
+
+        loop {
+            match self.cache[ix] {
+                Bucket::Empty => return Err((ix, dist)),
+                Bucket::Value(h, d, ref k, _) => {
+                    // Anything past here sits closer to home, so the key isn't in the map.
+                    if d < dist {
+                        return Err((ix, dist));
+                    }
+
+                    if h == hash && k.borrow() == key {
+                        return Ok(ix);
+                    }
+                }
+            }
+
+            ix = self.next_ix(ix);
+            dist += 1;
+        }
+    }
+
+    /// Puts a new entry into the bucket `ix` found by `probe`, displacing what follows.
+    fn insert_at(&mut self, ix: usize, hash: u64, dist: u32, key: K, value: V) {
+        self.count += 1;
+        self.displace(ix, hash, dist, key, value);
+    }
+
+    /// Takes the entry out of bucket `ix` and shifts the following displaced entries one
+    /// step back towards home.
+    fn remove_at(&mut self, mut hole: usize) -> (K, V) {
+        let entry = match mem::replace(&mut self.cache[hole], Bucket::Empty) {
+            Bucket::Value(_, _, k, v) => (k, v),
+            Bucket::Empty => panic!("Item that we wanted to remove is gone!"),
+        };
+        self.count -= 1;
+
+        loop {
+            let next = self.next_ix(hole);
+
+            match self.cache[next] {
+                Bucket::Value(_, ref mut d, _, _) if *d > 0 => *d -= 1,
+                _ => break,
+            }
+
+            self.cache.swap(hole, next);
+            hole = next;
+        }
+
+        entry
+    }
+
+    fn key(&self, ix: usize) -> &K {
+        match self.cache[ix] {
+            Bucket::Value(_, _, ref k, _) => k,
+            Bucket::Empty => panic!("key of an empty bucket"),
+        }
+    }
+
+    fn value(&self, ix: usize) -> &V {
+        match self.cache[ix] {
+            Bucket::Value(_, _, _, ref v) => v,
+            Bucket::Empty => panic!("value of an empty bucket"),
+        }
+    }
+
+    fn value_mut(&mut self, ix: usize) -> &mut V {
+        match self.cache[ix] {
+            Bucket::Value(_, _, _, ref mut v) => v,
+            Bucket::Empty => panic!("value of an empty bucket"),
+        }
+    }
+
+    /// Places an entry known not to be in the map, starting `dist` buckets from home at `ix`.
+    ///
+    /// Whenever the entry carried along is further from home than the one in the bucket
+    /// they swap, and the evicted entry is carried on instead.
+    fn displace(&mut self, mut ix: usize, mut hash: u64, mut dist: u32, mut key: K, mut value: V) {
+        loop {
+            match self.cache[ix] {
+                Bucket::Empty => {
+                    self.cache[ix] = Bucket::Value(hash, dist, key, value);
+                    return;
+                }
+                Bucket::Value(ref mut h, ref mut d, ref mut k, ref mut v) => {
+                    if *d < dist {
+                        mem::swap(h, &mut hash);
+                        mem::swap(d, &mut dist);
+                        mem::swap(k, &mut key);
+                        mem::swap(v, &mut value);
+                    }
+                }
+            }
+
+            ix = self.next_ix(ix);
+            dist += 1;
+        }
+    }
+
+    #[inline]
+    fn ix(&self, hash: u64) -> usize {
+        (hash & self.mod_mask) as usize
+    }
+
+    #[inline]
+    fn next_ix(&self, ix: usize) -> usize {
+        (ix + 1) & (self.mod_mask as usize)
+    }
+
+    /// Smallest table size that holds `entries` under the load limit.
+    fn size_for(&self, entries: usize) -> Result<u32, TryReserveError> {
+        let mut size = 1;
+
+        while self.config.max_load(1 << size) < entries {
+            size += 1;
+
+            if size >= usize::BITS {
+                return Err(TryReserveError::CapacityOverflow);
+            }
+        }
+
+        Ok(size)
+    }
+
+    #[inline]
+    fn check_load(&mut self) {
+        if let Err(err) = self.try_check_load() {
+            err.bail();
+        }
+    }
+
+    /// Makes room for the next insert, following the configured check policy.
+    ///
+    /// If the table can't grow an error is returned and the map is left as it was.
+    #[inline]
+    fn try_check_load(&mut self) -> Result<(), TryReserveError> {
+        match self.config.check_policy {
+            CheckPolicy::EveryInsert => self.try_ensure_load_rate(1),
+            CheckPolicy::Amortized(n) => {
+                // Resizing zeroes the headroom, forcing a new check.
+                if self.headroom == 0 {
+                    let n = n.min(self.grow_at.max(1));
+                    self.try_ensure_load_rate(n)?;
+                    self.headroom = n;
+                }
+
+                self.headroom -= 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Makes room for `additional` new entries without passing the load limit.
+    fn try_ensure_load_rate(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let needed = self.count.checked_add(additional).ok_or(TryReserveError::CapacityOverflow)?;
+
+        if needed > self.grow_at {
+            let size = self.size_for(needed)?.max(self.size + self.config.growth_shift);
+            self.try_resize(size)?;
+        }
+
+        Ok(())
+    }
+
+    /// Halves the table if the load rate fell below the configured min load factor.
+    fn ensure_min_load(&mut self) {
+        if self.size > 1 && (self.count as f64) < self.cache.len() as f64 * self.config.min_load_factor {
+            let size = self.size - 1;
+            self.resize(size);
+        }
+    }
+
+    /// Rehashes every entry into a fresh table of `2^size` buckets.
+    fn resize(&mut self, size: u32) {
+        if let Err(err) = self.try_resize(size) {
+            err.bail();
+        }
+    }
+
+    /// Rehashes every entry into a fresh table of `2^size` buckets, reusing the cached hashes.
+    ///
+    /// If the allocation fails the map is left untouched.
+    fn try_resize(&mut self, size: u32) -> Result<(), TryReserveError> {
+        let lim = 1usize.checked_shl(size).ok_or(TryReserveError::CapacityOverflow)?;
+        let cache = try_vec(lim, || Bucket::Empty)?;
+
+        self.size = size;
+        self.mod_mask = (lim as u64) - 1;
+        self.grow_at = self.config.max_load(lim);
+        self.headroom = 0;
+
+        for item in mem::replace(&mut self.cache, cache) {
+            if let Bucket::Value(h, _, k, v) = item {
+                let ix = self.ix(h);
+                self.displace(ix, h, 0, k, v);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+
+impl<K, V, S> Extend<(K, V)> for RobinHoodMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher {
+    /// Inserts all pairs, reserving room for the iterator's lower size bound first.
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+
+        // Keys already in the map don't take new buckets, so only count on half of them being new.
+        let hint = iter.size_hint().0;
+        self.reserve(if self.is_empty() { hint } else { hint.div_ceil(2) });
+
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<'a, K, V, S> Extend<(&'a K, &'a V)> for RobinHoodMap<K, V, S>
+    where K: Eq + Hash + Copy, V: Copy, S: BuildHasher {
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|(&k, &v)| (k, v)));
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for RobinHoodMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher + Default {
+    /// Builds a map from key/value pairs, later pairs replacing earlier ones with the same key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::RobinHoodMap;
+    ///
+    /// let map: RobinHoodMap<u64, u64> = (0..10).map(|i| (i, i * 2)).collect();
+    /// assert_eq!(map[&4], 8);
+    /// ```
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = RobinHoodMap::with_hasher(S::default());
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, S, const N: usize> From<[(K, V); N]> for RobinHoodMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher + Default {
+    fn from(arr: [(K, V); N]) -> Self {
+        IntoIterator::into_iter(arr).collect()
+    }
+}
+
+impl<K, V, S> Default for RobinHoodMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher + Default {
+    fn default() -> Self {
+        RobinHoodMap::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> Clone for RobinHoodMap<K, V, S>
+    where K: Eq + Hash + Clone, V: Clone, S: Clone {
+    /// Copies the bucket layout as it is, nothing is rehashed.
+    fn clone(&self) -> Self {
+        RobinHoodMap {
+            cache: self.cache.clone(),
+            size: self.size,
+            mod_mask: self.mod_mask,
+            count: self.count,
+            grow_at: self.grow_at,
+            headroom: self.headroom,
+            config: self.config,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<K, V, S> fmt::Debug for RobinHoodMap<K, V, S>
+    where K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S> PartialEq for RobinHoodMap<K, V, S>
+    where K: Eq + Hash, V: PartialEq, S: BuildHasher {
+    /// Maps are equal when they hold the same entries, wherever those sit in the table.
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<K, V, S> Eq for RobinHoodMap<K, V, S>
+    where K: Eq + Hash, V: Eq, S: BuildHasher {}
+
+impl<K, Q, V, S> Index<&Q> for RobinHoodMap<K, V, S>
+    where K: Eq + Hash + Borrow<Q>, Q: Eq + Hash + ?Sized, S: BuildHasher {
+    type Output = V;
+
+    /// Returns the value for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not in the map.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in RobinHoodMap")
+    }
+}
+
+impl<K, V, S> IntoIterator for RobinHoodMap<K, V, S>
+    where K: Eq + Hash {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    /// Consumes the map, yielding its entries in bucket order.
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            outer: self.cache.into_iter(),
+            remaining: self.count,
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a RobinHoodMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut RobinHoodMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+
+// ***************** Entry *********************
+
+/// A view into a single entry in a RobinHoodMap, which may either be vacant or occupied.
+///
+/// Constructed from the `entry` method on `RobinHoodMap`.
+pub enum Entry<'a, K: 'a + Eq + Hash, V: 'a, S: 'a = BuildMurmur2_64a> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+/// A view into an occupied entry in a RobinHoodMap.
+pub struct OccupiedEntry<'a, K: 'a + Eq + Hash, V: 'a, S: 'a = BuildMurmur2_64a> {
+    map: &'a mut RobinHoodMap<K, V, S>,
+    ix: usize,
+}
+
+/// A view into a vacant entry in a RobinHoodMap.
+pub struct VacantEntry<'a, K: 'a + Eq + Hash, V: 'a, S: 'a = BuildMurmur2_64a> {
+    map: &'a mut RobinHoodMap<K, V, S>,
+    hash: u64,
+    dist: u32,
+    key: K,
+    ix: usize,
+}
+
+/// The error returned by `try_insert` when the key already exists.
+///
+/// Contains the occupied entry and the value that was not inserted.
+pub struct OccupiedError<'a, K: 'a + Eq + Hash, V: 'a, S: 'a = BuildMurmur2_64a> {
+    /// The entry in the map that was already occupied.
+    pub entry: OccupiedEntry<'a, K, V, S>,
+    /// The value which was not inserted, because the entry was already occupied.
+    pub value: V,
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S>
+    where K: Eq + Hash {
+    /// Ensures a value is in the entry by inserting the default if empty,
+    /// and returns a mutable reference to the value in the entry.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of `default` if empty,
+    /// and returns a mutable reference to the value in the entry.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting `V::default()` if empty,
+    /// and returns a mutable reference to the value in the entry.
+    pub fn or_default(self) -> &'a mut V
+        where V: Default {
+        self.or_insert_with(V::default)
+    }
+
+    /// Runs `f` on the value if the entry is occupied, before any potential insert.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref entry) => entry.key(),
+            Entry::Vacant(ref entry) => entry.key(),
+        }
+    }
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S>
+    where K: Eq + Hash {
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        self.map.key(self.ix)
+    }
+
+    /// Returns a reference to the value in the entry.
+    pub fn get(&self) -> &V {
+        self.map.value(self.ix)
+    }
+
+    /// Returns a mutable reference to the value in the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map.value_mut(self.ix)
+    }
+
+    /// Converts the entry into a mutable reference to its value, bound to the map's lifetime.
+    pub fn into_mut(self) -> &'a mut V {
+        self.map.value_mut(self.ix)
+    }
+
+    /// Sets the value of the entry and returns the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    /// Takes the value out of the map.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Takes the key and value out of the map.
+    pub fn remove_entry(self) -> (K, V) {
+        let entry = self.map.remove_at(self.ix);
+        self.map.ensure_min_load();
+        entry
+    }
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S>
+    where K: Eq + Hash {
+    /// Returns a reference to the key that would be used when inserting.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Takes ownership of the key.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the value into the map and returns a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let map = self.map;
+
+        // The new entry always lands in the bucket found, whatever it displaces moves on.
+        map.insert_at(self.ix, self.hash, self.dist, self.key, value);
+        map.value_mut(self.ix)
+    }
+}
+
+impl<'a, K, V, S> fmt::Debug for OccupiedError<'a, K, V, S>
+    where K: Eq + Hash + fmt::Debug, V: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+
+impl<'a, K, V, S> fmt::Display for OccupiedError<'a, K, V, S>
+    where K: Eq + Hash + fmt::Debug, V: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value, self.entry.key(), self.entry.get())
+    }
+}
+
+impl<'a, K, V, S> Error for OccupiedError<'a, K, V, S>
+    where K: Eq + Hash + fmt::Debug, V: fmt::Debug {}
+
+
+// ***************** Iter *********************
+
+pub struct Iter<'a, K: 'a, V: 'a> {
+    outer: SliceIter<'a, Bucket<K, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Bucket::Value(_, _, ref k, ref v) = *self.outer.next()? {
+                self.remaining -= 1;
+                return Some((k, v));
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Bucket::Value(_, _, ref k, ref v) = *self.outer.next_back()? {
+                self.remaining -= 1;
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> Clone for Iter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Iter { outer: self.outer.clone(), remaining: self.remaining }
+    }
+}
+
+
+// ***************** Iter Mut *********************
+
+pub struct IterMut<'a, K: 'a, V: 'a> {
+    outer: SliceIterMut<'a, Bucket<K, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        loop {
+            if let Bucket::Value(_, _, ref k, ref mut v) = *self.outer.next()? {
+                self.remaining -= 1;
+                return Some((k, v));
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        loop {
+            if let Bucket::Value(_, _, ref k, ref mut v) = *self.outer.next_back()? {
+                self.remaining -= 1;
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IterMut<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for IterMut<'a, K, V> {}
+
+
+// ***************** Into Iter *********************
+
+pub struct IntoIter<K, V> {
+    outer: VecIntoIter<Bucket<K, V>>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            if let Bucket::Value(_, _, k, v) = self.outer.next()? {
+                self.remaining -= 1;
+                return Some((k, v));
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(K, V)> {
+        loop {
+            if let Bucket::Value(_, _, k, v) = self.outer.next_back()? {
+                self.remaining -= 1;
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+
+// ***************** Drain *********************
+
+pub struct Drain<'a, K: 'a, V: 'a, S: 'a = BuildMurmur2_64a>
+    where K: Eq + Hash, S: BuildHasher {
+    map: &'a mut RobinHoodMap<K, V, S>,
+    ix: usize,
+}
+
+impl<'a, K, V, S> Iterator for Drain<'a, K, V, S>
+    where K: Eq + Hash, S: BuildHasher {
+    type Item = (K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.ix < self.map.cache.len() {
+            let ix = self.ix;
+            self.ix += 1;
+
+            // No shifting back, every bucket ends up empty anyway.
+            if let Bucket::Value(_, _, k, v) = mem::replace(&mut self.map.cache[ix], Bucket::Empty) {
+                self.map.count -= 1;
+                return Some((k, v));
+            }
+        }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.map.count, Some(self.map.count))
+    }
+}
+
+impl<'a, K, V, S> ExactSizeIterator for Drain<'a, K, V, S>
+    where K: Eq + Hash, S: BuildHasher {}
+
+impl<'a, K, V, S> FusedIterator for Drain<'a, K, V, S>
+    where K: Eq + Hash, S: BuildHasher {}
+
+impl<'a, K, V, S> Drop for Drain<'a, K, V, S>
+    where K: Eq + Hash, S: BuildHasher {
+    /// Drops what wasn't drained.
+    fn drop(&mut self) {
+        self.map.clear();
+    }
+}
+
+
+// ***************** Extract If *********************
+
+pub struct ExtractIf<'a, K: 'a, V: 'a, F, S: 'a = BuildMurmur2_64a>
+    where K: Eq + Hash, F: FnMut(&K, &mut V) -> bool {
+    map: &'a mut RobinHoodMap<K, V, S>,
+    ix: usize,
+    // Buckets still to look at, all but the empty one the walk started after.
+    left: usize,
+    pred: F,
+}
+
+impl<'a, K, V, F, S> Iterator for ExtractIf<'a, K, V, F, S>
+    where K: Eq + Hash, F: FnMut(&K, &mut V) -> bool {
+    type Item = (K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.left > 0 {
+            let ix = self.ix;
+
+            if let Bucket::Value(_, _, ref k, ref mut v) = self.map.cache[ix] {
+                // The entry after it shifts back into this bucket, so the walk stays put.
+                if (self.pred)(k, v) {
+                    return Some(self.map.remove_at(ix));
+                }
+            }
+
+            self.ix = self.map.next_ix(ix);
+            self.left -= 1;
+        }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.map.count))
+    }
+}
+
+impl<'a, K, V, F, S> FusedIterator for ExtractIf<'a, K, V, F, S>
+    where K: Eq + Hash, F: FnMut(&K, &mut V) -> bool {}
+
+impl<'a, K, V, F, S> Drop for ExtractIf<'a, K, V, F, S>
+    where K: Eq + Hash, F: FnMut(&K, &mut V) -> bool {
+    /// Shrinks the table now that the walk is over, if the min load factor asks for it.
+    fn drop(&mut self) {
+        self.map.ensure_min_load();
+    }
+}
+
+
+// ***************** Values Iter *********************
+
+pub struct Values<'a, K: 'a, V: 'a> {
+    inner: Iter<'a, K, V>
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    #[inline] fn next(&mut self) -> Option<&'a V> { self.inner.next().map(|kv| kv.1) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Values<'a, K, V> {
+    #[inline] fn next_back(&mut self) -> Option<&'a V> { self.inner.next_back().map(|kv| kv.1) }
+}
+
+impl<'a, K, V> ExactSizeIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> Clone for Values<'a, K, V> {
+    fn clone(&self) -> Self {
+        Values { inner: self.inner.clone() }
+    }
+}
+
+
+// ***************** Values Mut Iter *********************
+
+pub struct ValuesMut<'a, K: 'a, V: 'a> {
+    inner: IterMut<'a, K, V>
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    #[inline] fn next(&mut self) -> Option<&'a mut V> { self.inner.next().map(|kv| kv.1) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<'a, K, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    #[inline] fn next_back(&mut self) -> Option<&'a mut V> { self.inner.next_back().map(|kv| kv.1) }
+}
+
+impl<'a, K, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for ValuesMut<'a, K, V> {}
+
+
+// ***************** Keys Iter *********************
+
+pub struct Keys<'a, K: 'a, V: 'a> {
+    inner: Iter<'a, K, V>
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    #[inline] fn next(&mut self) -> Option<&'a K> { self.inner.next().map(|kv| kv.0) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Keys<'a, K, V> {
+    #[inline] fn next_back(&mut self) -> Option<&'a K> { self.inner.next_back().map(|kv| kv.0) }
+}
+
+impl<'a, K, V> ExactSizeIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> Clone for Keys<'a, K, V> {
+    fn clone(&self) -> Self {
+        Keys { inner: self.inner.clone() }
+    }
+}
+
+
+// ***************** Into Keys *********************
+
+pub struct IntoKeys<K, V> {
+    inner: IntoIter<K, V>
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    #[inline] fn next(&mut self) -> Option<K> { self.inner.next().map(|kv| kv.0) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<K, V> DoubleEndedIterator for IntoKeys<K, V> {
+    #[inline] fn next_back(&mut self) -> Option<K> { self.inner.next_back().map(|kv| kv.0) }
+}
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {}
+
+impl<K, V> FusedIterator for IntoKeys<K, V> {}
+
+
+// ***************** Into Values *********************
+
+pub struct IntoValues<K, V> {
+    inner: IntoIter<K, V>
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    #[inline] fn next(&mut self) -> Option<V> { self.inner.next().map(|kv| kv.1) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<K, V> DoubleEndedIterator for IntoValues<K, V> {
+    #[inline] fn next_back(&mut self) -> Option<V> { self.inner.next_back().map(|kv| kv.1) }
+}
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {}
+
+impl<K, V> FusedIterator for IntoValues<K, V> {}
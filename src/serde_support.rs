@@ -0,0 +1,153 @@
+//! Serde support, behind the `serde` feature.
+//!
+//! `FastMap` serializes as a map and `FastSet` as a sequence, so they round-trip through
+//! any format that `std::collections::HashMap` and `HashSet` do. Deserializing reserves
+//! room for the length the format reports up front. Duplicate keys are accepted by default,
+//! later entries replacing earlier ones, use `deserialize_unique` or `deserialize_unique_set`
+//! to reject them instead.
+
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, Error, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use storage::Storage;
+use {FastMap, FastSet};
+
+// Caps the up front reservation, so a bogus length from the input can't exhaust memory.
+const MAX_PRESIZE: usize = 1 << 16;
+
+
+// ***************** FastMap *********************
+
+impl<K, V, S, T> Serialize for FastMap<K, V, S, T>
+    where K: Eq + Hash + Serialize, V: Serialize, S: BuildHasher, T: Storage<K, V> {
+    fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+
+        for (k, v) in self.iter() {
+            map.serialize_entry(k, v)?;
+        }
+
+        map.end()
+    }
+}
+
+impl<'de, K, V, S, T> Deserialize<'de> for FastMap<K, V, S, T>
+    where K: Eq + Hash + Deserialize<'de>, V: Deserialize<'de>, S: BuildHasher + Default, T: Storage<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor { unique: false, marker: PhantomData })
+    }
+}
+
+/// Deserializes a FastMap, failing on a key that appears more than once.
+///
+/// Fits `#[serde(deserialize_with = "fastmap::serde_support::deserialize_unique")]`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate fastmap;
+/// extern crate serde_json;
+///
+/// use fastmap::FastMap;
+/// use fastmap::serde_support::deserialize_unique;
+///
+/// # fn main() {
+/// let mut de = serde_json::Deserializer::from_str(r#"{"21": 42, "21": 43}"#);
+/// let res: Result<FastMap<u64, u64>, _> = deserialize_unique(&mut de);
+/// assert!(res.is_err());
+/// # }
+/// ```
+pub fn deserialize_unique<'de, D, K, V, S, T>(deserializer: D) -> Result<FastMap<K, V, S, T>, D::Error>
+    where D: Deserializer<'de>, K: Eq + Hash + Deserialize<'de>, V: Deserialize<'de>,
+          S: BuildHasher + Default, T: Storage<K, V> {
+    deserializer.deserialize_map(MapVisitor { unique: true, marker: PhantomData })
+}
+
+struct MapVisitor<K, V, S, T> {
+    unique: bool,
+    marker: PhantomData<(K, V, S, T)>,
+}
+
+impl<'de, K, V, S, T> Visitor<'de> for MapVisitor<K, V, S, T>
+    where K: Eq + Hash + Deserialize<'de>, V: Deserialize<'de>, S: BuildHasher + Default, T: Storage<K, V> {
+    type Value = FastMap<K, V, S, T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut map = FastMap::with_hasher(S::default());
+        map.reserve(access.size_hint().unwrap_or(0).min(MAX_PRESIZE));
+
+        while let Some((k, v)) = access.next_entry()? {
+            if map.insert(k, v).is_some() && self.unique {
+                return Err(A::Error::custom("duplicate key in map"));
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+
+// ***************** FastSet *********************
+
+impl<K, S> Serialize for FastSet<K, S>
+    where K: Eq + Hash + Serialize, S: BuildHasher {
+    fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+
+        for v in self.iter() {
+            seq.serialize_element(v)?;
+        }
+
+        seq.end()
+    }
+}
+
+impl<'de, K, S> Deserialize<'de> for FastSet<K, S>
+    where K: Eq + Hash + Deserialize<'de>, S: BuildHasher + Default {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(SetVisitor { unique: false, marker: PhantomData })
+    }
+}
+
+/// Deserializes a FastSet, failing on a value that appears more than once.
+///
+/// Fits `#[serde(deserialize_with = "fastmap::serde_support::deserialize_unique_set")]`.
+pub fn deserialize_unique_set<'de, D, K, S>(deserializer: D) -> Result<FastSet<K, S>, D::Error>
+    where D: Deserializer<'de>, K: Eq + Hash + Deserialize<'de>, S: BuildHasher + Default {
+    deserializer.deserialize_seq(SetVisitor { unique: true, marker: PhantomData })
+}
+
+struct SetVisitor<K, S> {
+    unique: bool,
+    marker: PhantomData<(K, S)>,
+}
+
+impl<'de, K, S> Visitor<'de> for SetVisitor<K, S>
+    where K: Eq + Hash + Deserialize<'de>, S: BuildHasher + Default {
+    type Value = FastSet<K, S>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut set = FastSet::with_hasher(S::default());
+        set.reserve(access.size_hint().unwrap_or(0).min(MAX_PRESIZE));
+
+        while let Some(v) = access.next_element()? {
+            if !set.insert(v) && self.unique {
+                return Err(A::Error::custom("duplicate value in set"));
+            }
+        }
+
+        Ok(set)
+    }
+}
@@ -0,0 +1,585 @@
+//! A hash set built on `FastMap`.
+//!
+//! `FastSet<T, S>` is a `FastMap<T, (), S>` with the set API of `std::collections::HashSet`.
+//! The set algebra methods return lazy iterators, the operators build new sets.
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::{Chain, FromIterator, FusedIterator};
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+use {BuildMurmur2_64a, FastMap, IntoKeys, Keys};
+
+/// A hash set, implemented as a `FastMap` where the value is `()`.
+///
+/// # Examples
+///
+/// ```
+/// use fastmap::FastSet;
+///
+/// let mut set = FastSet::new();
+/// set.insert("Bart");
+/// set.insert("Lisa");
+///
+/// assert!(set.contains("Bart"));
+/// assert!(!set.insert("Lisa"));
+/// assert_eq!(set.len(), 2);
+/// ```
+pub struct FastSet<T: Eq + Hash, S = BuildMurmur2_64a> {
+    map: FastMap<T, (), S>,
+}
+
+impl<T> FastSet<T>
+    where T: Eq + Hash {
+    /// Creates a new FastSet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let mut set: FastSet<u64> = FastSet::new();
+    /// ```
+    pub fn new() -> Self {
+        FastSet { map: FastMap::new() }
+    }
+
+
+    /// Creates a new FastSet with a at least capacity, all sizes is a power of 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let mut set: FastSet<u64> = FastSet::with_capacity(20);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        FastSet { map: FastMap::with_capacity(capacity) }
+    }
+}
+
+impl<T, S> FastSet<T, S>
+    where T: Eq + Hash, S: BuildHasher {
+    /// Creates a new FastSet which will use the given hash builder to hash values.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        FastSet { map: FastMap::with_hasher(hash_builder) }
+    }
+
+
+    /// Creates a new FastSet with at least capacity, using the given hash builder to hash values.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        FastSet { map: FastMap::with_capacity_and_hasher(capacity, hash_builder) }
+    }
+
+    /// Returns a reference to the set's hash builder.
+    pub fn hasher(&self) -> &S {
+        self.map.hasher()
+    }
+
+
+    /// Reserves room for at least `additional` more values, so they can be inserted
+    /// without the table growing.
+    pub fn reserve(&mut self, additional: usize) {
+        self.map.reserve(additional)
+    }
+
+
+    /// Adds a value to the set, returning true if it wasn't there yet.
+    ///
+    /// An equal value already in the set is kept, see `replace` to swap it out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let mut set = FastSet::new();
+    /// assert!(set.insert(21));
+    /// assert!(!set.insert(21));
+    /// ```
+    pub fn insert(&mut self, value: T) -> bool {
+        self.map.insert(value, ()).is_none()
+    }
+
+
+    /// Adds a value to the set, replacing and returning an equal value already there.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let mut set = FastSet::new();
+    /// assert_eq!(set.replace(21), None);
+    /// assert_eq!(set.replace(21), Some(21));
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        self.map.replace_key(value, ()).map(|(old, _)| old)
+    }
+
+
+    /// Returns true if the value is in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let mut set: FastSet<u64> = FastSet::new();
+    /// set.insert(21);
+    /// assert!(set.contains(&21));
+    /// assert!(!set.contains(&42));
+    /// ```
+    pub fn contains<Q>(&self, value: &Q) -> bool
+        where T: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.map.contains_key(value)
+    }
+
+
+    /// Returns a reference to the value in the set that is equal to the given one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let mut set: FastSet<String> = FastSet::new();
+    /// set.insert("Bart".to_string());
+    /// assert_eq!(set.get("Bart"), Some(&"Bart".to_string()));
+    /// assert_eq!(set.get("Lisa"), None);
+    /// ```
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+        where T: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.map.get_key_value(value).map(|(k, _)| k)
+    }
+
+
+    /// Removes a value from the set, returning true if it was there.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let mut set: FastSet<u64> = FastSet::new();
+    /// set.insert(21);
+    /// assert!(set.remove(&21));
+    /// assert!(!set.remove(&21));
+    /// ```
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+        where T: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.map.remove(value).is_some()
+    }
+
+
+    /// Removes a value from the set and returns it, if it was there.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let mut set: FastSet<String> = FastSet::new();
+    /// set.insert("Bart".to_string());
+    /// assert_eq!(set.take("Bart"), Some("Bart".to_string()));
+    /// assert_eq!(set.take("Bart"), None);
+    /// ```
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+        where T: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.map.remove_entry(value).map(|(k, _)| k)
+    }
+
+
+    /// Removes all values from the set.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+
+    /// Returns true if set is empty
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+
+    /// Number of values in the set.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+
+    /// Total number of slots available.
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+
+    //**** Iterators *****
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { inner: self.map.keys() }
+    }
+
+
+    /// Visits the values in `self` or `other`, each once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let a: FastSet<u64> = [1, 2, 3].iter().cloned().collect();
+    /// let b: FastSet<u64> = [2, 3, 4].iter().cloned().collect();
+    ///
+    /// let mut union: Vec<u64> = a.union(&b).cloned().collect();
+    /// union.sort();
+    /// assert_eq!(union, [1, 2, 3, 4]);
+    /// ```
+    pub fn union<'a>(&'a self, other: &'a FastSet<T, S>) -> Union<'a, T, S> {
+        Union { inner: self.iter().chain(other.difference(self)) }
+    }
+
+
+    /// Visits the values in both `self` and `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let a: FastSet<u64> = [1, 2, 3].iter().cloned().collect();
+    /// let b: FastSet<u64> = [2, 3, 4].iter().cloned().collect();
+    ///
+    /// let mut intersection: Vec<u64> = a.intersection(&b).cloned().collect();
+    /// intersection.sort();
+    /// assert_eq!(intersection, [2, 3]);
+    /// ```
+    pub fn intersection<'a>(&'a self, other: &'a FastSet<T, S>) -> Intersection<'a, T, S> {
+        // Walk the smaller set and look the values up in the larger one.
+        let (iter, other) = if self.len() <= other.len() { (self.iter(), other) } else { (other.iter(), self) };
+        Intersection { iter, other }
+    }
+
+
+    /// Visits the values in `self` but not in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let a: FastSet<u64> = [1, 2, 3].iter().cloned().collect();
+    /// let b: FastSet<u64> = [2, 3, 4].iter().cloned().collect();
+    ///
+    /// assert_eq!(a.difference(&b).collect::<Vec<_>>(), [&1]);
+    /// ```
+    pub fn difference<'a>(&'a self, other: &'a FastSet<T, S>) -> Difference<'a, T, S> {
+        Difference { iter: self.iter(), other }
+    }
+
+
+    /// Visits the values in `self` or `other`, but not in both.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let a: FastSet<u64> = [1, 2, 3].iter().cloned().collect();
+    /// let b: FastSet<u64> = [2, 3, 4].iter().cloned().collect();
+    ///
+    /// let mut diff: Vec<u64> = a.symmetric_difference(&b).cloned().collect();
+    /// diff.sort();
+    /// assert_eq!(diff, [1, 4]);
+    /// ```
+    pub fn symmetric_difference<'a>(&'a self, other: &'a FastSet<T, S>) -> SymmetricDifference<'a, T, S> {
+        SymmetricDifference { inner: self.difference(other).chain(other.difference(self)) }
+    }
+
+
+    /// Returns true if every value in `self` is also in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let a: FastSet<u64> = [1, 2].iter().cloned().collect();
+    /// let b: FastSet<u64> = [1, 2, 3].iter().cloned().collect();
+    ///
+    /// assert!(a.is_subset(&b));
+    /// assert!(!b.is_subset(&a));
+    /// ```
+    pub fn is_subset(&self, other: &FastSet<T, S>) -> bool {
+        self.len() <= other.len() && self.iter().all(|v| other.contains(v))
+    }
+
+
+    /// Returns true if every value in `other` is also in `self`.
+    pub fn is_superset(&self, other: &FastSet<T, S>) -> bool {
+        other.is_subset(self)
+    }
+
+
+    /// Returns true if `self` and `other` have no values in common.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastSet;
+    ///
+    /// let a: FastSet<u64> = [1, 2].iter().cloned().collect();
+    /// let b: FastSet<u64> = [3, 4].iter().cloned().collect();
+    ///
+    /// assert!(a.is_disjoint(&b));
+    /// ```
+    pub fn is_disjoint(&self, other: &FastSet<T, S>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
+
+
+impl<T, S> Extend<T> for FastSet<T, S>
+    where T: Eq + Hash, S: BuildHasher {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.map.extend(iter.into_iter().map(|v| (v, ())));
+    }
+}
+
+impl<T, S> FromIterator<T> for FastSet<T, S>
+    where T: Eq + Hash, S: BuildHasher + Default {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = FastSet::with_hasher(S::default());
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T, S> Default for FastSet<T, S>
+    where T: Eq + Hash, S: BuildHasher + Default {
+    fn default() -> Self {
+        FastSet::with_hasher(S::default())
+    }
+}
+
+impl<T, S> Clone for FastSet<T, S>
+    where T: Eq + Hash + Clone, S: Clone {
+    fn clone(&self) -> Self {
+        FastSet { map: self.map.clone() }
+    }
+}
+
+impl<T, S> fmt::Debug for FastSet<T, S>
+    where T: Eq + Hash + fmt::Debug, S: BuildHasher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T, S> PartialEq for FastSet<T, S>
+    where T: Eq + Hash, S: BuildHasher {
+    /// Sets are equal when they hold the same values.
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T, S> Eq for FastSet<T, S>
+    where T: Eq + Hash, S: BuildHasher {}
+
+impl<T, S> IntoIterator for FastSet<T, S>
+    where T: Eq + Hash, S: BuildHasher {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { inner: self.map.into_keys() }
+    }
+}
+
+impl<'a, T, S> IntoIterator for &'a FastSet<T, S>
+    where T: Eq + Hash, S: BuildHasher {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+
+// ***************** Operators *********************
+
+impl<T, S> BitOr<&FastSet<T, S>> for &FastSet<T, S>
+    where T: Eq + Hash + Clone, S: BuildHasher + Default {
+    type Output = FastSet<T, S>;
+
+    /// Returns the union of `self` and `rhs` as a new set.
+    fn bitor(self, rhs: &FastSet<T, S>) -> FastSet<T, S> {
+        self.union(rhs).cloned().collect()
+    }
+}
+
+impl<T, S> BitAnd<&FastSet<T, S>> for &FastSet<T, S>
+    where T: Eq + Hash + Clone, S: BuildHasher + Default {
+    type Output = FastSet<T, S>;
+
+    /// Returns the intersection of `self` and `rhs` as a new set.
+    fn bitand(self, rhs: &FastSet<T, S>) -> FastSet<T, S> {
+        self.intersection(rhs).cloned().collect()
+    }
+}
+
+impl<T, S> Sub<&FastSet<T, S>> for &FastSet<T, S>
+    where T: Eq + Hash + Clone, S: BuildHasher + Default {
+    type Output = FastSet<T, S>;
+
+    /// Returns the difference of `self` and `rhs` as a new set.
+    fn sub(self, rhs: &FastSet<T, S>) -> FastSet<T, S> {
+        self.difference(rhs).cloned().collect()
+    }
+}
+
+impl<T, S> BitXor<&FastSet<T, S>> for &FastSet<T, S>
+    where T: Eq + Hash + Clone, S: BuildHasher + Default {
+    type Output = FastSet<T, S>;
+
+    /// Returns the symmetric difference of `self` and `rhs` as a new set.
+    fn bitxor(self, rhs: &FastSet<T, S>) -> FastSet<T, S> {
+        self.symmetric_difference(rhs).cloned().collect()
+    }
+}
+
+
+// ***************** Iter *********************
+
+pub struct Iter<'a, T: 'a>
+    where T: Eq + Hash {
+    inner: Keys<'a, T, ()>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T>
+    where T: Eq + Hash {
+    type Item = &'a T;
+
+    #[inline] fn next(&mut self) -> Option<&'a T> { self.inner.next() }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T>
+    where T: Eq + Hash {}
+
+impl<'a, T> FusedIterator for Iter<'a, T>
+    where T: Eq + Hash {}
+
+impl<'a, T> Clone for Iter<'a, T>
+    where T: Eq + Hash {
+    fn clone(&self) -> Self {
+        Iter { inner: self.inner.clone() }
+    }
+}
+
+
+// ***************** Into Iter *********************
+
+pub struct IntoIter<T>
+    where T: Eq + Hash {
+    inner: IntoKeys<T, ()>,
+}
+
+impl<T> Iterator for IntoIter<T>
+    where T: Eq + Hash {
+    type Item = T;
+
+    #[inline] fn next(&mut self) -> Option<T> { self.inner.next() }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T>
+    where T: Eq + Hash {}
+
+impl<T> FusedIterator for IntoIter<T>
+    where T: Eq + Hash {}
+
+
+// ***************** Union *********************
+
+pub struct Union<'a, T: 'a, S: 'a>
+    where T: Eq + Hash {
+    inner: Chain<Iter<'a, T>, Difference<'a, T, S>>,
+}
+
+impl<'a, T, S> Iterator for Union<'a, T, S>
+    where T: Eq + Hash, S: BuildHasher {
+    type Item = &'a T;
+
+    #[inline] fn next(&mut self) -> Option<&'a T> { self.inner.next() }
+}
+
+
+// ***************** Intersection *********************
+
+pub struct Intersection<'a, T: 'a, S: 'a>
+    where T: Eq + Hash {
+    iter: Iter<'a, T>,
+    other: &'a FastSet<T, S>,
+}
+
+impl<'a, T, S> Iterator for Intersection<'a, T, S>
+    where T: Eq + Hash, S: BuildHasher {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            let v = self.iter.next()?;
+
+            if self.other.contains(v) {
+                return Some(v);
+            }
+        }
+    }
+}
+
+
+// ***************** Difference *********************
+
+pub struct Difference<'a, T: 'a, S: 'a>
+    where T: Eq + Hash {
+    iter: Iter<'a, T>,
+    other: &'a FastSet<T, S>,
+}
+
+impl<'a, T, S> Iterator for Difference<'a, T, S>
+    where T: Eq + Hash, S: BuildHasher {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            let v = self.iter.next()?;
+
+            if !self.other.contains(v) {
+                return Some(v);
+            }
+        }
+    }
+}
+
+
+// ***************** Symmetric Difference *********************
+
+pub struct SymmetricDifference<'a, T: 'a, S: 'a>
+    where T: Eq + Hash {
+    inner: Chain<Difference<'a, T, S>, Difference<'a, T, S>>,
+}
+
+impl<'a, T, S> Iterator for SymmetricDifference<'a, T, S>
+    where T: Eq + Hash, S: BuildHasher {
+    type Item = &'a T;
+
+    #[inline] fn next(&mut self) -> Option<&'a T> { self.inner.next() }
+}
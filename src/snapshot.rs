@@ -0,0 +1,710 @@
+//! Binary snapshots of a FastMap, see `FastMap::save_to` and `FastMap::load_from`.
+//!
+//! A snapshot stores the bucket array as it is, so loading puts every entry back into the
+//! bucket it was saved from and never hashes a key. Only maps of plain `Copy` keys and
+//! values, `Pod`, hashed by a seeded hasher, `SnapshotHasher`, can be saved.
+//!
+//! A `SnapshotView` looks keys up in the saved buckets where they are, a snapshot in a
+//! memory-mapped file can be read without building a map at all.
+//!
+//! The format, all integers little-endian:
+//!
+//! * header: magic `FASTMAP\0`, version `u32`, hasher id `u32`, seed `u64`, key size `u32`,
+//!   value size `u32`, key type id `u64`, value type id `u64`, table size as a power of 2
+//!   `u32`, count `u64`, old table length `u64` and old buckets migrated `u64`, the last two
+//!   non zero only while resizing incrementally.
+//! * config: max and min load factor `f64`, growth factor as a power of 2 `u32`, amortized
+//!   check interval `u64`, 0 for checking every insert, and incremental resize step `u64`.
+//! * buckets: the table, then the old table, one fixed width record per bucket. A tag byte,
+//!   0 empty, 1 deleted or 2 full, then the cached hash, key and value, zeroed unless full.
+//! * checksum: xxHash64 of everything before it, `u64`.
+
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::io::{self, Read, Write};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::slice::ChunksExact;
+
+use hashers::{BuildFibonacciHasher, BuildMurmur3_x64_128, BuildXxHash64, XxHash64};
+use storage::{Slot, Storage};
+use {BuildMurmur2_64a, CheckPolicy, FastMap, FastMapConfig, SnapshotError};
+#[cfg(feature = "random")]
+use RandomMurmur;
+
+const MAGIC: [u8; 8] = *b"FASTMAP\0";
+
+/// The snapshot format version written by `save_to`, the only one `load_from` reads.
+pub const VERSION: u32 = 2;
+
+/// Bytes taken by the header and config.
+const HEADER_LEN: usize = 112;
+
+const TAG_EMPTY: u8 = 0;
+const TAG_DELETED: u8 = 1;
+const TAG_FULL: u8 = 2;
+
+
+// ***************** Pod *********************
+
+/// A plain value with a fixed width little-endian encoding, storable in a snapshot.
+pub trait Pod: Copy {
+    /// Bytes taken by the encoding.
+    const SIZE: usize;
+
+    /// Identifies the type in the snapshot header, so a snapshot only loads as the types
+    /// it was saved from. Has to differ between any two `Pod` types.
+    const TYPE_ID: u64;
+
+    /// Encodes `self` into `out`, which is exactly `SIZE` bytes.
+    fn write_le(&self, out: &mut [u8]);
+
+    /// Decodes a value from `bytes`, which are exactly `SIZE` bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! pod_integers {
+    ($($int:ty => $id:expr),*) => {
+        $(
+            impl Pod for $int {
+                const SIZE: usize = ::std::mem::size_of::<$int>();
+                const TYPE_ID: u64 = $id;
+
+                #[inline]
+                fn write_le(&self, out: &mut [u8]) {
+                    out.copy_from_slice(&self.to_le_bytes());
+                }
+
+                #[inline]
+                fn read_le(bytes: &[u8]) -> $int {
+                    let mut buf = [0u8; ::std::mem::size_of::<$int>()];
+                    buf.copy_from_slice(bytes);
+                    <$int>::from_le_bytes(buf)
+                }
+            }
+        )*
+    };
+}
+
+pod_integers!(u8 => 1, u16 => 2, u32 => 3, u64 => 4, u128 => 5, i8 => 6, i16 => 7, i32 => 8, i64 => 9, i128 => 10);
+
+// Pointer sized integers are stored as 64 bits, so snapshots move between platforms.
+impl Pod for usize {
+    const SIZE: usize = 8;
+    const TYPE_ID: u64 = 11;
+
+    #[inline]
+    fn write_le(&self, out: &mut [u8]) {
+        (*self as u64).write_le(out);
+    }
+
+    #[inline]
+    fn read_le(bytes: &[u8]) -> usize {
+        u64::read_le(bytes) as usize
+    }
+}
+
+impl Pod for isize {
+    const SIZE: usize = 8;
+    const TYPE_ID: u64 = 12;
+
+    #[inline]
+    fn write_le(&self, out: &mut [u8]) {
+        (*self as i64).write_le(out);
+    }
+
+    #[inline]
+    fn read_le(bytes: &[u8]) -> isize {
+        i64::read_le(bytes) as isize
+    }
+}
+
+impl<P: Pod, const N: usize> Pod for [P; N] {
+    const SIZE: usize = P::SIZE * N;
+    // Mixes the element id and length, keeping clear of the small ids of the integers.
+    const TYPE_ID: u64 = (P::TYPE_ID.rotate_left(32) ^ N as u64 ^ 0xA55A).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+
+    fn write_le(&self, out: &mut [u8]) {
+        for (p, chunk) in self.iter().zip(out.chunks_exact_mut(P::SIZE)) {
+            p.write_le(chunk);
+        }
+    }
+
+    fn read_le(bytes: &[u8]) -> [P; N] {
+        ::std::array::from_fn(|i| P::read_le(&bytes[i * P::SIZE..(i + 1) * P::SIZE]))
+    }
+}
+
+
+// ***************** SnapshotHasher *********************
+
+/// A hash builder that is fully described by its seed, so a snapshot can name it.
+///
+/// Builders with the same `ID` and seed hash alike, a map saved with `RandomMurmur`
+/// loads as one hashed by `BuildMurmur2_64a`.
+pub trait SnapshotHasher: BuildHasher {
+    /// Identifies the hash function in the snapshot header.
+    const ID: u32;
+
+    /// The seed mixed into every hash.
+    fn snapshot_seed(&self) -> u64;
+
+    /// A builder hashing exactly like the one that returned `seed`.
+    fn from_snapshot_seed(seed: u64) -> Self;
+}
+
+macro_rules! snapshot_hashers {
+    ($($build:ident => $id:expr),*) => {
+        $(
+            impl SnapshotHasher for $build {
+                const ID: u32 = $id;
+
+                fn snapshot_seed(&self) -> u64 {
+                    self.seed()
+                }
+
+                fn from_snapshot_seed(seed: u64) -> $build {
+                    $build::with_seed(seed)
+                }
+            }
+        )*
+    };
+}
+
+snapshot_hashers!(BuildMurmur2_64a => 1, BuildMurmur3_x64_128 => 2, BuildXxHash64 => 3, BuildFibonacciHasher => 4);
+
+#[cfg(feature = "random")]
+impl SnapshotHasher for RandomMurmur {
+    const ID: u32 = 1;
+
+    fn snapshot_seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn from_snapshot_seed(seed: u64) -> RandomMurmur {
+        RandomMurmur { seed }
+    }
+}
+
+
+// ***************** FastMap *********************
+
+impl<K, V, S, T> FastMap<K, V, S, T>
+    where K: Eq + Hash + Pod, V: Pod, S: SnapshotHasher, T: Storage<K, V> {
+    /// Writes the map to `writer` as a snapshot, buckets in place, see the `snapshot` module.
+    ///
+    /// Writes a record per bucket, hand it a `BufWriter` when writing to a file or socket.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastmap::FastMap;
+    ///
+    /// let mut map: FastMap<u64, u64> = FastMap::new();
+    /// map.insert(21, 42);
+    ///
+    /// let mut buf = Vec::new();
+    /// map.save_to(&mut buf).unwrap();
+    ///
+    /// let loaded: FastMap<u64, u64> = FastMap::load_from(&buf[..]).unwrap();
+    /// assert_eq!(loaded, map);
+    /// ```
+    pub fn save_to<W: Write>(&self, writer: W) -> Result<(), SnapshotError> {
+        let mut out = Checksummed { inner: writer, hasher: XxHash64::new() };
+
+        let check_interval = match self.config.check_policy {
+            CheckPolicy::EveryInsert => 0,
+            CheckPolicy::Amortized(n) => n as u64,
+        };
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(&MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&S::ID.to_le_bytes());
+        header.extend_from_slice(&self.hasher.snapshot_seed().to_le_bytes());
+        header.extend_from_slice(&(K::SIZE as u32).to_le_bytes());
+        header.extend_from_slice(&(V::SIZE as u32).to_le_bytes());
+        header.extend_from_slice(&K::TYPE_ID.to_le_bytes());
+        header.extend_from_slice(&V::TYPE_ID.to_le_bytes());
+        header.extend_from_slice(&self.size.to_le_bytes());
+        header.extend_from_slice(&(self.count as u64).to_le_bytes());
+        header.extend_from_slice(&(self.old.len() as u64).to_le_bytes());
+        header.extend_from_slice(&(self.migrated as u64).to_le_bytes());
+        header.extend_from_slice(&self.config.max_load_factor.to_le_bytes());
+        header.extend_from_slice(&self.config.min_load_factor.to_le_bytes());
+        header.extend_from_slice(&self.config.growth_shift.to_le_bytes());
+        header.extend_from_slice(&check_interval.to_le_bytes());
+        header.extend_from_slice(&(self.config.resize_step as u64).to_le_bytes());
+        debug_assert_eq!(header.len(), HEADER_LEN);
+        out.write(&header)?;
+
+        let mut record = vec![0u8; record_len::<K, V>()];
+
+        for table in &[&self.cache, &self.old] {
+            for ix in 0..table.len() {
+                encode(*table, ix, &mut record);
+                out.write(&record)?;
+            }
+        }
+
+        let sum = out.hasher.finish();
+        out.inner.write_all(&sum.to_le_bytes())?;
+        out.inner.flush()?;
+
+        Ok(())
+    }
+
+    /// Reads a map saved by `save_to`, putting every entry back into the bucket it was
+    /// saved from without hashing it.
+    ///
+    /// Fails unless the snapshot was written in this format version, by the same hash
+    /// function and key and value types, and matches its checksum. The table also has to
+    /// hold as many entries as the header counts, keep an empty bucket and stay within
+    /// the saved load limit. The hasher is rebuilt with the saved seed and the map gets
+    /// the saved config.
+    ///
+    /// The input is checked before the table is allocated, so it is read into a buffer
+    /// first, that only grows as bytes arrive.
+    pub fn load_from<R: Read>(mut reader: R) -> Result<Self, SnapshotError> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN);
+        reader.by_ref().take(HEADER_LEN as u64).read_to_end(&mut bytes)?;
+        let header = Header::read::<K, V, S>(&mut &bytes[..])?;
+
+        let body_len = header.body_len::<K, V>().map_or(u64::MAX, |n| n as u64);
+        reader.take(body_len).read_to_end(&mut bytes)?;
+
+        let view = SnapshotView::<K, V, S>::new(&bytes)?;
+        let (table, old) = view.buckets.split_at(view.table_bytes);
+
+        let mut map = FastMap::with_hasher(S::from_snapshot_seed(header.seed));
+        map.config = header.config;
+        map.try_swap_cache(header.size)?;
+        map.old = T::try_with_len(header.old_len as usize)?;
+        map.migrated = header.migrated as usize;
+
+        fill(&mut map.cache, table);
+        fill(&mut map.old, old);
+
+        map.count = header.count as usize;
+        map.deleted = view.deleted;
+
+        Ok(map)
+    }
+}
+
+
+// ***************** SnapshotView *********************
+
+/// A read-only map over the bytes of a snapshot, looking keys up in the saved buckets
+/// without copying them into a table, e.g. over a memory-mapped file.
+///
+/// Keys and values are decoded on the way out, so they are returned by value.
+///
+/// # Examples
+///
+/// ```
+/// use fastmap::FastMap;
+/// use fastmap::snapshot::SnapshotView;
+///
+/// let mut map: FastMap<u64, u64> = FastMap::new();
+/// map.insert(21, 42);
+///
+/// let mut buf = Vec::new();
+/// map.save_to(&mut buf).unwrap();
+///
+/// let view: SnapshotView<u64, u64> = SnapshotView::new(&buf).unwrap();
+/// assert_eq!(view.get(&21), Some(42));
+/// assert_eq!(view.get(&42), None);
+/// ```
+pub struct SnapshotView<'a, K, V, S = BuildMurmur2_64a> {
+    // The table's buckets followed by the old table's.
+    buckets: &'a [u8],
+    table_bytes: usize,
+    count: usize,
+    // Tombstones in the table, not counting the old table's.
+    deleted: usize,
+    hasher: S,
+    entries: PhantomData<(K, V)>,
+}
+
+impl<'a, K, V, S> SnapshotView<'a, K, V, S>
+    where K: Eq + Hash + Pod, V: Pod, S: SnapshotHasher {
+    /// Checks `bytes` the way `FastMap::load_from` checks its input and borrows the
+    /// buckets in it. Reads every bucket once to do so, but copies none.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, SnapshotError> {
+        let header = Header::read::<K, V, S>(&mut &bytes[..])?;
+
+        // Both tables and the checksum have to be there, a length past usize can't be.
+        let end = match header.body_len::<K, V>() {
+            Some(n) if n <= bytes.len() - HEADER_LEN => HEADER_LEN + n - 8,
+            _ => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+        };
+
+        let mut hasher = XxHash64::new();
+        hasher.write(&bytes[..end]);
+        if u64::read_le(&bytes[end..end + 8]) != hasher.finish() {
+            return Err(SnapshotError::ChecksumMismatch);
+        }
+
+        let rec = record_len::<K, V>();
+        let lim = 1usize << header.size;
+        let buckets = &bytes[HEADER_LEN..end];
+
+        let mut tallies = [Tally::default(), Tally::default()];
+        for (ix, record) in buckets.chunks_exact(rec).enumerate() {
+            tallies[(ix >= lim) as usize].add(slot_of(record)?);
+        }
+
+        header.check(&tallies)?;
+
+        Ok(SnapshotView {
+            buckets,
+            table_bytes: lim * rec,
+            count: header.count as usize,
+            deleted: tallies[0].deleted,
+            hasher: S::from_snapshot_seed(header.seed),
+            entries: PhantomData,
+        })
+    }
+
+
+    /// Returns the value stored for `key`.
+    pub fn get(&self, key: &K) -> Option<V> {
+        let hash = self.hasher.hash_one(key);
+        let (table, old) = self.buckets.split_at(self.table_bytes);
+
+        find::<K, V>(table, hash, key).or_else(|| find::<K, V>(old, hash, key))
+    }
+
+
+    /// Returns true if the snapshot holds `key`.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+
+    /// Number of entries in the snapshot.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+
+    /// Returns true if the snapshot holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+
+    /// Returns the hash builder rebuilt from the saved seed.
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+
+    /// Iterates over the entries in bucket order, like `FastMap::iter` on the saved map.
+    pub fn iter(&self) -> ViewIter<'a, K, V> {
+        ViewIter {
+            records: self.buckets.chunks_exact(record_len::<K, V>()),
+            remaining: self.count,
+            entries: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V, S> fmt::Debug for SnapshotView<'a, K, V, S>
+    where K: Eq + Hash + Pod + fmt::Debug, V: Pod + fmt::Debug, S: SnapshotHasher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &SnapshotView<'a, K, V, S>
+    where K: Eq + Hash + Pod, V: Pod, S: SnapshotHasher {
+    type Item = (K, V);
+    type IntoIter = ViewIter<'a, K, V>;
+
+    fn into_iter(self) -> ViewIter<'a, K, V> {
+        self.iter()
+    }
+}
+
+
+// ***************** View Iter *********************
+
+pub struct ViewIter<'a, K, V> {
+    records: ChunksExact<'a, u8>,
+    remaining: usize,
+    entries: PhantomData<(K, V)>,
+}
+
+impl<'a, K: Pod, V: Pod> Iterator for ViewIter<'a, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        for record in self.records.by_ref() {
+            if record[0] == TAG_FULL {
+                self.remaining -= 1;
+                let (key, value) = record[9..].split_at(K::SIZE);
+                return Some((K::read_le(key), V::read_le(value)));
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K: Pod, V: Pod> ExactSizeIterator for ViewIter<'a, K, V> {}
+
+impl<'a, K: Pod, V: Pod> FusedIterator for ViewIter<'a, K, V> {}
+
+impl<'a, K, V> Clone for ViewIter<'a, K, V> {
+    fn clone(&self) -> Self {
+        ViewIter { records: self.records.clone(), remaining: self.remaining, entries: PhantomData }
+    }
+}
+
+
+// ***************** Format *********************
+
+/// The header fields that describe the tables, checked for range when read.
+struct Header {
+    seed: u64,
+    size: u32,
+    count: u64,
+    old_len: u64,
+    migrated: u64,
+    config: FastMapConfig,
+}
+
+impl Header {
+    /// Reads the header, failing on anything a map of `K`, `V` hashed by `S` can't load.
+    fn read<K: Pod, V: Pod, S: SnapshotHasher>(input: &mut &[u8]) -> Result<Header, SnapshotError> {
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+
+        let version = read_u32(input)?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let id = read_u32(input)?;
+        if id != S::ID {
+            return Err(SnapshotError::HasherMismatch { expected: S::ID, found: id });
+        }
+
+        let seed = read_u64(input)?;
+        let key_size = read_u32(input)?;
+        let value_size = read_u32(input)?;
+        let key_type = read_u64(input)?;
+        let value_type = read_u64(input)?;
+        if key_size as usize != K::SIZE || value_size as usize != V::SIZE || key_type != K::TYPE_ID || value_type != V::TYPE_ID {
+            return Err(SnapshotError::LayoutMismatch);
+        }
+
+        let size = read_u32(input)?;
+        let count = read_u64(input)?;
+        let old_len = read_u64(input)?;
+        let migrated = read_u64(input)?;
+
+        if size == 0 || size >= usize::BITS {
+            return Err(SnapshotError::Corrupt("table size out of range"));
+        }
+        if old_len != 0 && (!old_len.is_power_of_two() || old_len >= 1 << size) {
+            return Err(SnapshotError::Corrupt("old table length out of range"));
+        }
+        if migrated > old_len || (old_len > 0 && migrated == old_len) {
+            return Err(SnapshotError::Corrupt("old buckets migrated out of range"));
+        }
+
+        let max_load_factor = f64::from_bits(read_u64(input)?);
+        let min_load_factor = f64::from_bits(read_u64(input)?);
+        let growth_shift = read_u32(input)?;
+        let check_interval = read_u64(input)?;
+        let resize_step = read_u64(input)?;
+
+        // The ranges `FastMapConfig` asserts, written so NaN fails them too.
+        let factors_ok = max_load_factor > 0.0 && max_load_factor < 1.0
+            && min_load_factor >= 0.0 && min_load_factor * 2.0 < max_load_factor;
+        if !factors_ok || growth_shift == 0 || growth_shift >= usize::BITS {
+            return Err(SnapshotError::Corrupt("config out of range"));
+        }
+
+        let config = FastMapConfig {
+            max_load_factor,
+            min_load_factor,
+            growth_shift,
+            check_policy: match check_interval {
+                0 => CheckPolicy::EveryInsert,
+                n => CheckPolicy::Amortized(n as usize),
+            },
+            resize_step: resize_step as usize,
+            ..FastMapConfig::new()
+        };
+
+        Ok(Header { seed, size, count, old_len, migrated, config })
+    }
+
+    /// Bytes following the header: both tables and the checksum, None past `usize`.
+    fn body_len<K: Pod, V: Pod>(&self) -> Option<usize> {
+        (1usize << self.size)
+            .checked_add(self.old_len as usize)
+            .and_then(|n| n.checked_mul(record_len::<K, V>()))
+            .and_then(|n| n.checked_add(8))
+    }
+
+    /// Checks the buckets read, the table's then the old table's, against the header.
+    ///
+    /// Probing only stops at an empty bucket, and a table past the load limit would break
+    /// it on the next insert.
+    fn check(&self, tallies: &[Tally; 2]) -> Result<(), SnapshotError> {
+        let [table, old] = tallies;
+
+        if table.empty == 0 || (self.old_len > 0 && old.empty == 0) {
+            return Err(SnapshotError::Corrupt("no empty bucket to end a probe"));
+        }
+        if table.full + old.full != self.count {
+            return Err(SnapshotError::Corrupt("count doesn't match the full buckets"));
+        }
+        if self.count + table.deleted as u64 > self.config.max_load(1 << self.size) as u64 {
+            return Err(SnapshotError::Corrupt("table filled past its load limit"));
+        }
+
+        Ok(())
+    }
+}
+
+/// What the buckets of one table hold.
+#[derive(Default)]
+struct Tally {
+    full: u64,
+    deleted: usize,
+    empty: usize,
+}
+
+impl Tally {
+    #[inline]
+    fn add(&mut self, slot: Slot) {
+        match slot {
+            Slot::Full(_) => self.full += 1,
+            Slot::Deleted => self.deleted += 1,
+            Slot::Empty => self.empty += 1,
+        }
+    }
+}
+
+#[inline]
+fn record_len<K: Pod, V: Pod>() -> usize {
+    1 + 8 + K::SIZE + V::SIZE
+}
+
+/// Encodes bucket `ix` of `table` into `record`.
+fn encode<K: Pod, V: Pod, T: Storage<K, V>>(table: &T, ix: usize, record: &mut [u8]) {
+    let (tag, rest) = record.split_first_mut().unwrap();
+    let (hash, rest) = rest.split_at_mut(8);
+    let (key, value) = rest.split_at_mut(K::SIZE);
+
+    match table.slot(ix) {
+        Slot::Full(h) => {
+            *tag = TAG_FULL;
+            hash.copy_from_slice(&h.to_le_bytes());
+            table.key(ix).write_le(key);
+            table.value(ix).write_le(value);
+        }
+        slot => {
+            *tag = if slot == Slot::Deleted { TAG_DELETED } else { TAG_EMPTY };
+            for b in rest.iter_mut().chain(hash.iter_mut()) {
+                *b = 0;
+            }
+        }
+    }
+}
+
+/// Reads the tag and hash of `record`.
+fn slot_of(record: &[u8]) -> Result<Slot, SnapshotError> {
+    match record[0] {
+        TAG_EMPTY => Ok(Slot::Empty),
+        TAG_DELETED => Ok(Slot::Deleted),
+        TAG_FULL => Ok(Slot::Full(u64::read_le(&record[1..9]))),
+        _ => Err(SnapshotError::Corrupt("unknown bucket tag")),
+    }
+}
+
+/// Puts the checked records in `buckets` into the same buckets of the empty `table`.
+fn fill<K: Pod, V: Pod, T: Storage<K, V>>(table: &mut T, buckets: &[u8]) {
+    for (ix, record) in buckets.chunks_exact(record_len::<K, V>()).enumerate() {
+        let key = K::read_le(&record[9..9 + K::SIZE]);
+        let value = V::read_le(&record[9 + K::SIZE..]);
+
+        match record[0] {
+            TAG_FULL => table.put(ix, u64::read_le(&record[1..9]), key, value),
+            TAG_DELETED => {
+                // Storage only leaves tombstones behind a take.
+                table.put(ix, 0, key, value);
+                table.take(ix);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Looks `key` up in the stored `buckets`, probing like `FastMap` does.
+fn find<K: Eq + Pod, V: Pod>(buckets: &[u8], hash: u64, key: &K) -> Option<V> {
+    let rec = record_len::<K, V>();
+    let len = buckets.len() / rec;
+    if len == 0 {
+        return None;
+    }
+
+    let mut ix = (hash as usize) & (len - 1);
+
+    for _ in 0..len {
+        let record = &buckets[ix * rec..(ix + 1) * rec];
+
+        match record[0] {
+            TAG_EMPTY => return None,
+            TAG_FULL if u64::read_le(&record[1..9]) == hash => {
+                let (k, value) = record[9..].split_at(K::SIZE);
+                if K::read_le(k) == *key {
+                    return Some(V::read_le(value));
+                }
+            }
+            _ => {}
+        }
+
+        ix = (ix + 1) & (len - 1);
+    }
+
+    None
+}
+
+/// Writes through to `inner`, checksumming every byte on the way.
+struct Checksummed<I> {
+    inner: I,
+    hasher: XxHash64,
+}
+
+impl<W: Write> Checksummed<W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.hasher.write(bytes);
+        self.inner.write_all(bytes)
+    }
+}
+
+fn read_u32(input: &mut &[u8]) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(input: &mut &[u8]) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    input.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
@@ -0,0 +1,653 @@
+//! Bucket storage for FastMap.
+//!
+//! `FastMap` takes the way it lays out its buckets as its last type parameter:
+//!
+//! * `Interleaved`, the default, keeps the cached hash, key and value of a bucket next to
+//!   each other in a single array.
+//! * `Split` is a structure of arrays. Bucket states, hashes, keys and values each get
+//!   their own array, so probing compares cached hashes without pulling keys and values
+//!   into cache. Worth it for large values, see `SplitMap`.
+//! * `Swiss` keeps a control byte per bucket and scans a whole group of them at once,
+//!   see `SwissMap`.
+
+use std::alloc::Layout;
+use std::iter::{FusedIterator, Zip};
+use std::mem::{self, MaybeUninit};
+use std::slice::Iter as SliceIter;
+use std::slice::IterMut as SliceIterMut;
+use std::vec::IntoIter as VecIntoIter;
+
+use TryReserveError;
+
+/// The storage `FastMap` uses when none is named.
+///
+/// `Interleaved`, or `Split` with the `split-storage` feature.
+#[cfg(not(feature = "split-storage"))]
+pub type DefaultStorage<K, V> = Interleaved<K, V>;
+
+/// The storage `FastMap` uses when none is named.
+///
+/// `Interleaved`, or `Split` with the `split-storage` feature.
+#[cfg(feature = "split-storage")]
+pub type DefaultStorage<K, V> = Split<K, V>;
+
+/// What a bucket holds, as far as probing cares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    /// An entry with this cached hash.
+    Full(u64),
+    /// A tombstone left by a removed entry.
+    Deleted,
+    /// Never used since the table was built.
+    Empty,
+}
+
+/// Bucket storage for a FastMap: a fixed number of buckets, each empty, deleted or full.
+///
+/// Reading the key or value of a bucket that isn't full panics.
+pub trait Storage<K, V>: Sized {
+    type Iter<'a>: DoubleEndedIterator<Item = (&'a K, &'a V)> + FusedIterator + Clone where Self: 'a, K: 'a, V: 'a;
+    type IterMut<'a>: DoubleEndedIterator<Item = (&'a K, &'a mut V)> + FusedIterator where Self: 'a, K: 'a, V: 'a;
+    type IntoEntries: DoubleEndedIterator<Item = (u64, K, V)> + FusedIterator;
+
+    /// Storage without any buckets, doesn't allocate.
+    fn new() -> Self;
+
+    /// Storage with `len` empty buckets, allocated up front.
+    fn try_with_len(len: usize) -> Result<Self, TryReserveError>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn slot(&self, ix: usize) -> Slot;
+
+    fn key(&self, ix: usize) -> &K;
+
+    fn value(&self, ix: usize) -> &V;
+
+    fn value_mut(&mut self, ix: usize) -> &mut V;
+
+    fn entry_mut(&mut self, ix: usize) -> (&K, &mut V);
+
+    /// Fills a bucket that isn't full.
+    fn put(&mut self, ix: usize, hash: u64, key: K, value: V);
+
+    /// Takes the entry out of a full bucket, leaving a tombstone.
+    fn take(&mut self, ix: usize) -> (u64, K, V);
+
+    /// Empties every bucket.
+    fn clear(&mut self);
+
+    fn iter(&self) -> Self::Iter<'_>;
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_>;
+
+    /// Consumes the storage, yielding every entry with its cached hash.
+    fn into_entries(self) -> Self::IntoEntries;
+
+    /// Probes linearly from `ix` for a full bucket with cached hash `hash` whose key `is_match`.
+    ///
+    /// Returns `Ok` with the bucket holding the key, or `Err` with the bucket to insert it
+    /// into: the first tombstone passed, else the empty bucket that ended the probe.
+    /// Storages that scan faster override this, visiting buckets in the same order.
+    #[inline]
+    fn probe<F>(&self, hash: u64, mut ix: usize, mut is_match: F) -> Result<usize, usize>
+        where F: FnMut(&K) -> bool {
+        let mask = self.len() - 1;
+        let mut free = None;
+
+        for _ in 0..self.len() {
+            match self.slot(ix) {
+                Slot::Full(h) => {
+                    if h == hash && is_match(self.key(ix)) {
+                        return Ok(ix);
+                    }
+                }
+                Slot::Deleted => {
+                    if free.is_none() {
+                        free = Some(ix);
+                    }
+                }
+                Slot::Empty => return Err(free.unwrap_or(ix)),
+            }
+
+            ix = (ix + 1) & mask;
+        }
+
+        // Went all the way around, only tombstones are left to insert into.
+        Err(free.expect("FastMap has no free bucket left!"))
+    }
+
+    /// The first bucket from `ix` on, wrapping around, that isn't full. There has to be one.
+    #[inline]
+    fn find_free(&self, mut ix: usize) -> usize {
+        let mask = self.len() - 1;
+
+        while let Slot::Full(_) = self.slot(ix) {
+            ix = (ix + 1) & mask;
+        }
+
+        ix
+    }
+}
+
+/// Allocates a vector of exactly `len` elements made by `fill`, or reports why it can't.
+pub(crate) fn try_vec<T, F: FnMut() -> T>(len: usize, fill: F) -> Result<Vec<T>, TryReserveError> {
+    let layout = Layout::array::<T>(len).map_err(|_| TryReserveError::CapacityOverflow)?;
+
+    let mut vec = Vec::new();
+    vec.try_reserve_exact(len).map_err(|_| TryReserveError::AllocError { layout })?;
+    vec.resize_with(len, fill);
+
+    Ok(vec)
+}
+
+
+// ***************** Interleaved *********************
+
+/// Buckets holding cached hash, key and value together, in a single array.
+#[derive(Clone)]
+pub struct Interleaved<K, V> {
+    buckets: Vec<Bucket<K, V>>,
+}
+
+#[derive(Clone)]
+enum Bucket<K, V> {
+    Value(u64, K, V),
+    Deleted,
+    Empty,
+}
+
+impl<K, V> Storage<K, V> for Interleaved<K, V> {
+    type Iter<'a> = InterleavedIter<'a, K, V> where K: 'a, V: 'a;
+    type IterMut<'a> = InterleavedIterMut<'a, K, V> where K: 'a, V: 'a;
+    type IntoEntries = InterleavedIntoEntries<K, V>;
+
+    fn new() -> Self {
+        Interleaved { buckets: Vec::new() }
+    }
+
+    fn try_with_len(len: usize) -> Result<Self, TryReserveError> {
+        Ok(Interleaved { buckets: try_vec(len, || Bucket::Empty)? })
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    #[inline]
+    fn slot(&self, ix: usize) -> Slot {
+        match self.buckets[ix] {
+            Bucket::Value(h, _, _) => Slot::Full(h),
+            Bucket::Deleted => Slot::Deleted,
+            Bucket::Empty => Slot::Empty,
+        }
+    }
+
+    #[inline]
+    fn key(&self, ix: usize) -> &K {
+        match self.buckets[ix] {
+            Bucket::Value(_, ref k, _) => k,
+            _ => panic!("Bucket {} holds no value!", ix),
+        }
+    }
+
+    #[inline]
+    fn value(&self, ix: usize) -> &V {
+        match self.buckets[ix] {
+            Bucket::Value(_, _, ref v) => v,
+            _ => panic!("Bucket {} holds no value!", ix),
+        }
+    }
+
+    #[inline]
+    fn value_mut(&mut self, ix: usize) -> &mut V {
+        match self.buckets[ix] {
+            Bucket::Value(_, _, ref mut v) => v,
+            _ => panic!("Bucket {} holds no value!", ix),
+        }
+    }
+
+    #[inline]
+    fn entry_mut(&mut self, ix: usize) -> (&K, &mut V) {
+        match self.buckets[ix] {
+            Bucket::Value(_, ref k, ref mut v) => (k, v),
+            _ => panic!("Bucket {} holds no value!", ix),
+        }
+    }
+
+    #[inline]
+    fn put(&mut self, ix: usize, hash: u64, key: K, value: V) {
+        self.buckets[ix] = Bucket::Value(hash, key, value);
+    }
+
+    #[inline]
+    fn take(&mut self, ix: usize) -> (u64, K, V) {
+        match mem::replace(&mut self.buckets[ix], Bucket::Deleted) {
+            Bucket::Value(h, k, v) => (h, k, v),
+            _ => panic!("Bucket {} holds no value!", ix),
+        }
+    }
+
+    fn clear(&mut self) {
+        for bucket in self.buckets.iter_mut() {
+            *bucket = Bucket::Empty;
+        }
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        InterleavedIter { outer: self.buckets.iter() }
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        InterleavedIterMut { outer: self.buckets.iter_mut() }
+    }
+
+    fn into_entries(self) -> Self::IntoEntries {
+        InterleavedIntoEntries { outer: self.buckets.into_iter() }
+    }
+}
+
+pub struct InterleavedIter<'a, K: 'a, V: 'a> {
+    outer: SliceIter<'a, Bucket<K, V>>,
+}
+
+impl<'a, K, V> Iterator for InterleavedIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Bucket::Value(_, ref k, ref v) = *self.outer.next()? {
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for InterleavedIter<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Bucket::Value(_, ref k, ref v) = *self.outer.next_back()? {
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> FusedIterator for InterleavedIter<'a, K, V> {}
+
+impl<'a, K, V> Clone for InterleavedIter<'a, K, V> {
+    fn clone(&self) -> Self {
+        InterleavedIter { outer: self.outer.clone() }
+    }
+}
+
+pub struct InterleavedIterMut<'a, K: 'a, V: 'a> {
+    outer: SliceIterMut<'a, Bucket<K, V>>,
+}
+
+impl<'a, K, V> Iterator for InterleavedIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        loop {
+            if let Bucket::Value(_, ref k, ref mut v) = *self.outer.next()? {
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for InterleavedIterMut<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        loop {
+            if let Bucket::Value(_, ref k, ref mut v) = *self.outer.next_back()? {
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> FusedIterator for InterleavedIterMut<'a, K, V> {}
+
+pub struct InterleavedIntoEntries<K, V> {
+    outer: VecIntoIter<Bucket<K, V>>,
+}
+
+impl<K, V> Iterator for InterleavedIntoEntries<K, V> {
+    type Item = (u64, K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(u64, K, V)> {
+        loop {
+            if let Bucket::Value(h, k, v) = self.outer.next()? {
+                return Some((h, k, v));
+            }
+        }
+    }
+}
+
+impl<K, V> DoubleEndedIterator for InterleavedIntoEntries<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(u64, K, V)> {
+        loop {
+            if let Bucket::Value(h, k, v) = self.outer.next_back()? {
+                return Some((h, k, v));
+            }
+        }
+    }
+}
+
+impl<K, V> FusedIterator for InterleavedIntoEntries<K, V> {}
+
+
+// ***************** Split *********************
+
+// Bucket states in `Split`, a byte per bucket.
+const EMPTY: u8 = 0;
+const DELETED: u8 = 1;
+const FULL: u8 = 2;
+
+/// Buckets as a structure of arrays: state, cached hash, key and value each in their own array.
+///
+/// Only the state array says which buckets are full, so probing reads one byte per bucket
+/// plus the cached hash of full ones, and never touches keys or values unless the hashes match.
+pub struct Split<K, V> {
+    states: Vec<u8>,
+    hashes: Vec<u64>,
+    // Initialized exactly where the state is FULL.
+    keys: Vec<MaybeUninit<K>>,
+    values: Vec<MaybeUninit<V>>,
+}
+
+impl<K, V> Split<K, V> {
+    #[inline]
+    fn assert_full(&self, ix: usize) {
+        if self.states[ix] != FULL {
+            panic!("Bucket {} holds no value!", ix);
+        }
+    }
+
+    /// Drops the entry of every full bucket and marks all of them empty.
+    fn drop_entries(&mut self) {
+        for ix in 0..self.states.len() {
+            if mem::replace(&mut self.states[ix], EMPTY) == FULL {
+                // SAFETY: the bucket was full and is marked empty before its entry is dropped.
+                unsafe {
+                    self.keys[ix].assume_init_drop();
+                    self.values[ix].assume_init_drop();
+                }
+            }
+        }
+    }
+}
+
+impl<K, V> Storage<K, V> for Split<K, V> {
+    type Iter<'a> = SplitIter<'a, K, V> where K: 'a, V: 'a;
+    type IterMut<'a> = SplitIterMut<'a, K, V> where K: 'a, V: 'a;
+    type IntoEntries = SplitIntoEntries<K, V>;
+
+    fn new() -> Self {
+        Split { states: Vec::new(), hashes: Vec::new(), keys: Vec::new(), values: Vec::new() }
+    }
+
+    fn try_with_len(len: usize) -> Result<Self, TryReserveError> {
+        // Overflow is about the whole table, not whichever array happens to be allocated first.
+        let bucket = mem::size_of::<u8>() + mem::size_of::<u64>() + mem::size_of::<K>() + mem::size_of::<V>();
+        match len.checked_mul(bucket) {
+            Some(bytes) if bytes <= isize::MAX as usize => (),
+            _ => return Err(TryReserveError::CapacityOverflow),
+        }
+
+        Ok(Split {
+            states: try_vec(len, || EMPTY)?,
+            hashes: try_vec(len, || 0)?,
+            keys: try_vec(len, MaybeUninit::uninit)?,
+            values: try_vec(len, MaybeUninit::uninit)?,
+        })
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    #[inline]
+    fn slot(&self, ix: usize) -> Slot {
+        match self.states[ix] {
+            FULL => Slot::Full(self.hashes[ix]),
+            DELETED => Slot::Deleted,
+            _ => Slot::Empty,
+        }
+    }
+
+    #[inline]
+    fn key(&self, ix: usize) -> &K {
+        self.assert_full(ix);
+        // SAFETY: the bucket is full.
+        unsafe { self.keys[ix].assume_init_ref() }
+    }
+
+    #[inline]
+    fn value(&self, ix: usize) -> &V {
+        self.assert_full(ix);
+        // SAFETY: the bucket is full.
+        unsafe { self.values[ix].assume_init_ref() }
+    }
+
+    #[inline]
+    fn value_mut(&mut self, ix: usize) -> &mut V {
+        self.assert_full(ix);
+        // SAFETY: the bucket is full.
+        unsafe { self.values[ix].assume_init_mut() }
+    }
+
+    #[inline]
+    fn entry_mut(&mut self, ix: usize) -> (&K, &mut V) {
+        self.assert_full(ix);
+        // SAFETY: the bucket is full.
+        unsafe { (self.keys[ix].assume_init_ref(), self.values[ix].assume_init_mut()) }
+    }
+
+    #[inline]
+    fn put(&mut self, ix: usize, hash: u64, key: K, value: V) {
+        debug_assert!(self.states[ix] != FULL, "Bucket {} is already full!", ix);
+
+        self.states[ix] = FULL;
+        self.hashes[ix] = hash;
+        self.keys[ix] = MaybeUninit::new(key);
+        self.values[ix] = MaybeUninit::new(value);
+    }
+
+    #[inline]
+    fn take(&mut self, ix: usize) -> (u64, K, V) {
+        self.assert_full(ix);
+        self.states[ix] = DELETED;
+
+        // SAFETY: the bucket was full, and its state now says it isn't.
+        unsafe { (self.hashes[ix], self.keys[ix].assume_init_read(), self.values[ix].assume_init_read()) }
+    }
+
+    fn clear(&mut self) {
+        self.drop_entries();
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        SplitIter { outer: self.states.iter().zip(self.keys.iter().zip(self.values.iter())) }
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        SplitIterMut { outer: self.states.iter().zip(self.keys.iter().zip(self.values.iter_mut())) }
+    }
+
+    fn into_entries(mut self) -> Self::IntoEntries {
+        // Leaves `self` without buckets, so dropping it drops no entries.
+        SplitIntoEntries {
+            states: mem::take(&mut self.states).into_iter(),
+            hashes: mem::take(&mut self.hashes).into_iter(),
+            keys: mem::take(&mut self.keys).into_iter(),
+            values: mem::take(&mut self.values).into_iter(),
+        }
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for Split<K, V> {
+    fn clone(&self) -> Self {
+        let mut keys = Vec::with_capacity(self.len());
+        let mut values = Vec::with_capacity(self.len());
+
+        for (ix, &state) in self.states.iter().enumerate() {
+            if state == FULL {
+                // SAFETY: the bucket is full.
+                unsafe {
+                    keys.push(MaybeUninit::new(self.keys[ix].assume_init_ref().clone()));
+                    values.push(MaybeUninit::new(self.values[ix].assume_init_ref().clone()));
+                }
+            } else {
+                keys.push(MaybeUninit::uninit());
+                values.push(MaybeUninit::uninit());
+            }
+        }
+
+        Split { states: self.states.clone(), hashes: self.hashes.clone(), keys, values }
+    }
+}
+
+impl<K, V> Drop for Split<K, V> {
+    fn drop(&mut self) {
+        if mem::needs_drop::<(K, V)>() {
+            self.drop_entries();
+        }
+    }
+}
+
+// States paired with keys and values, a key and value are there when the state is full.
+type SplitBuckets<'a, K, I> = Zip<SliceIter<'a, u8>, Zip<SliceIter<'a, MaybeUninit<K>>, I>>;
+
+pub struct SplitIter<'a, K: 'a, V: 'a> {
+    outer: SplitBuckets<'a, K, SliceIter<'a, MaybeUninit<V>>>,
+}
+
+impl<'a, K, V> Iterator for SplitIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let (&FULL, (k, v)) = self.outer.next()? {
+                // SAFETY: the bucket is full.
+                return Some(unsafe { (k.assume_init_ref(), v.assume_init_ref()) });
+            }
+        }
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for SplitIter<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let (&FULL, (k, v)) = self.outer.next_back()? {
+                // SAFETY: the bucket is full.
+                return Some(unsafe { (k.assume_init_ref(), v.assume_init_ref()) });
+            }
+        }
+    }
+}
+
+impl<'a, K, V> FusedIterator for SplitIter<'a, K, V> {}
+
+impl<'a, K, V> Clone for SplitIter<'a, K, V> {
+    fn clone(&self) -> Self {
+        SplitIter { outer: self.outer.clone() }
+    }
+}
+
+pub struct SplitIterMut<'a, K: 'a, V: 'a> {
+    outer: SplitBuckets<'a, K, SliceIterMut<'a, MaybeUninit<V>>>,
+}
+
+impl<'a, K, V> Iterator for SplitIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        loop {
+            if let (&FULL, (k, v)) = self.outer.next()? {
+                // SAFETY: the bucket is full.
+                return Some(unsafe { (k.assume_init_ref(), v.assume_init_mut()) });
+            }
+        }
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for SplitIterMut<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        loop {
+            if let (&FULL, (k, v)) = self.outer.next_back()? {
+                // SAFETY: the bucket is full.
+                return Some(unsafe { (k.assume_init_ref(), v.assume_init_mut()) });
+            }
+        }
+    }
+}
+
+impl<'a, K, V> FusedIterator for SplitIterMut<'a, K, V> {}
+
+pub struct SplitIntoEntries<K, V> {
+    states: VecIntoIter<u8>,
+    hashes: VecIntoIter<u64>,
+    keys: VecIntoIter<MaybeUninit<K>>,
+    values: VecIntoIter<MaybeUninit<V>>,
+}
+
+impl<K, V> Iterator for SplitIntoEntries<K, V> {
+    type Item = (u64, K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(u64, K, V)> {
+        loop {
+            let state = self.states.next()?;
+            let (h, k, v) = (self.hashes.next()?, self.keys.next()?, self.values.next()?);
+
+            if state == FULL {
+                // SAFETY: the bucket is full, and its state is consumed with the entry.
+                return Some(unsafe { (h, k.assume_init(), v.assume_init()) });
+            }
+        }
+    }
+}
+
+impl<K, V> DoubleEndedIterator for SplitIntoEntries<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(u64, K, V)> {
+        loop {
+            let state = self.states.next_back()?;
+            let (h, k, v) = (self.hashes.next_back()?, self.keys.next_back()?, self.values.next_back()?);
+
+            if state == FULL {
+                // SAFETY: the bucket is full, and its state is consumed with the entry.
+                return Some(unsafe { (h, k.assume_init(), v.assume_init()) });
+            }
+        }
+    }
+}
+
+impl<K, V> FusedIterator for SplitIntoEntries<K, V> {}
+
+impl<K, V> Drop for SplitIntoEntries<K, V> {
+    /// Drops the entries that weren't taken, the arrays alone wouldn't.
+    fn drop(&mut self) {
+        if mem::needs_drop::<(K, V)>() {
+            self.by_ref().for_each(drop);
+        }
+    }
+}
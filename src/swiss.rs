@@ -0,0 +1,527 @@
+//! SwissTable-style bucket storage for `FastMap`.
+//!
+//! Entries live in a plain slot array, and a separate array holds one control byte per
+//! bucket: the top 7 bits of the hash for a full bucket, or an empty/deleted marker. Probing
+//! loads a whole group of control bytes at once and matches all of them against the tag in
+//! a few instructions, using SSE2 where available and a portable 64-bit fallback elsewhere.
+//! Slots are only touched when their tag matches, so lookups mostly stay in the small
+//! control array.
+//!
+//! Groups are scanned in bucket order, so this is still the linear probing `FastMap` does,
+//! just a group at a time.
+
+use std::iter::{FusedIterator, Zip};
+use std::mem::{self, MaybeUninit};
+use std::slice::Iter as SliceIter;
+use std::slice::IterMut as SliceIterMut;
+use std::vec::IntoIter as VecIntoIter;
+
+use storage::{try_vec, Slot, Storage};
+use TryReserveError;
+
+use self::group::Group;
+
+// Control bytes: full buckets hold the top 7 bits of the hash, high bit clear.
+const EMPTY: u8 = 0b1111_1111;
+const DELETED: u8 = 0b1000_0000;
+
+/// Buckets with a control byte each, probed a group of control bytes at a time.
+///
+/// Use it through `SwissMap`.
+pub struct Swiss<K, V> {
+    // A byte per bucket, then a group's worth more repeating them from the start, so a
+    // group loads whole from any bucket. Empty when there are no buckets.
+    ctrl: Vec<u8>,
+    // Initialized exactly where the control byte is full.
+    slots: Vec<MaybeUninit<(u64, K, V)>>,
+}
+
+impl<K, V> Swiss<K, V> {
+    #[inline]
+    fn assert_full(&self, ix: usize) {
+        if !is_full(self.ctrl[ix]) {
+            panic!("Bucket {} holds no value!", ix);
+        }
+    }
+
+    /// Sets the control byte of a bucket, and every copy of it past the end.
+    #[inline]
+    fn set_ctrl(&mut self, ix: usize, c: u8) {
+        let len = self.slots.len();
+        let mut i = ix;
+
+        while i < self.ctrl.len() {
+            self.ctrl[i] = c;
+            i += len;
+        }
+    }
+
+    #[inline]
+    fn full(&self, ix: usize) -> &(u64, K, V) {
+        self.assert_full(ix);
+        // SAFETY: the bucket is full.
+        unsafe { self.slots[ix].assume_init_ref() }
+    }
+
+    #[inline]
+    fn full_mut(&mut self, ix: usize) -> &mut (u64, K, V) {
+        self.assert_full(ix);
+        // SAFETY: the bucket is full.
+        unsafe { self.slots[ix].assume_init_mut() }
+    }
+
+    /// Drops the entry of every full bucket and marks all of them empty.
+    fn drop_entries(&mut self) {
+        for ix in 0..self.slots.len() {
+            if is_full(self.ctrl[ix]) {
+                self.set_ctrl(ix, EMPTY);
+                // SAFETY: the bucket was full and is marked empty before its entry is dropped.
+                unsafe { self.slots[ix].assume_init_drop() };
+            }
+        }
+
+        for c in self.ctrl.iter_mut() {
+            *c = EMPTY;
+        }
+    }
+}
+
+impl<K, V> Storage<K, V> for Swiss<K, V> {
+    type Iter<'a> = SwissIter<'a, K, V> where K: 'a, V: 'a;
+    type IterMut<'a> = SwissIterMut<'a, K, V> where K: 'a, V: 'a;
+    type IntoEntries = SwissIntoEntries<K, V>;
+
+    fn new() -> Self {
+        Swiss { ctrl: Vec::new(), slots: Vec::new() }
+    }
+
+    fn try_with_len(len: usize) -> Result<Self, TryReserveError> {
+        if len == 0 {
+            return Ok(Swiss::new());
+        }
+
+        // Overflow is about the whole table, not whichever array happens to be allocated first.
+        let bucket = mem::size_of::<u8>() + mem::size_of::<(u64, K, V)>();
+        match len.checked_mul(bucket).and_then(|bytes| bytes.checked_add(Group::WIDTH)) {
+            Some(bytes) if bytes <= isize::MAX as usize => (),
+            _ => return Err(TryReserveError::CapacityOverflow),
+        }
+
+        Ok(Swiss {
+            ctrl: try_vec(len + Group::WIDTH, || EMPTY)?,
+            slots: try_vec(len, MaybeUninit::uninit)?,
+        })
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    #[inline]
+    fn slot(&self, ix: usize) -> Slot {
+        match self.ctrl[ix] {
+            c if is_full(c) => Slot::Full(self.full(ix).0),
+            DELETED => Slot::Deleted,
+            _ => Slot::Empty,
+        }
+    }
+
+    #[inline]
+    fn key(&self, ix: usize) -> &K {
+        &self.full(ix).1
+    }
+
+    #[inline]
+    fn value(&self, ix: usize) -> &V {
+        &self.full(ix).2
+    }
+
+    #[inline]
+    fn value_mut(&mut self, ix: usize) -> &mut V {
+        &mut self.full_mut(ix).2
+    }
+
+    #[inline]
+    fn entry_mut(&mut self, ix: usize) -> (&K, &mut V) {
+        let (_, ref k, ref mut v) = *self.full_mut(ix);
+        (k, v)
+    }
+
+    #[inline]
+    fn put(&mut self, ix: usize, hash: u64, key: K, value: V) {
+        debug_assert!(!is_full(self.ctrl[ix]), "Bucket {} is already full!", ix);
+
+        self.set_ctrl(ix, h2(hash));
+        self.slots[ix] = MaybeUninit::new((hash, key, value));
+    }
+
+    #[inline]
+    fn take(&mut self, ix: usize) -> (u64, K, V) {
+        self.assert_full(ix);
+        self.set_ctrl(ix, DELETED);
+
+        // SAFETY: the bucket was full, and its control byte now says it isn't.
+        unsafe { self.slots[ix].assume_init_read() }
+    }
+
+    fn clear(&mut self) {
+        self.drop_entries();
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        SwissIter { outer: self.ctrl[..self.slots.len()].iter().zip(self.slots.iter()) }
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        SwissIterMut { outer: self.ctrl[..self.slots.len()].iter().zip(self.slots.iter_mut()) }
+    }
+
+    fn into_entries(mut self) -> Self::IntoEntries {
+        let mut ctrl = mem::take(&mut self.ctrl);
+        ctrl.truncate(self.slots.len());
+
+        // Leaves `self` without buckets, so dropping it drops no entries.
+        SwissIntoEntries { ctrl: ctrl.into_iter(), slots: mem::take(&mut self.slots).into_iter() }
+    }
+
+    /// Scans a group of control bytes at a time, only reading slots whose tag matches.
+    #[inline]
+    fn probe<F>(&self, hash: u64, ix: usize, mut is_match: F) -> Result<usize, usize>
+        where F: FnMut(&K) -> bool {
+        let mask = self.slots.len() - 1;
+        let tag = h2(hash);
+        let mut pos = ix;
+        let mut free = None;
+        let mut scanned = 0;
+
+        loop {
+            let group = Group::load(&self.ctrl[pos..]);
+            // Buckets past the first empty one aren't part of the probe.
+            let end = group.match_empty().lowest_set_bit().unwrap_or(Group::WIDTH);
+
+            for bit in group.match_byte(tag) {
+                if bit >= end {
+                    break;
+                }
+
+                let b = (pos + bit) & mask;
+                let (h, ref k, _) = *self.full(b);
+                if h == hash && is_match(k) {
+                    return Ok(b);
+                }
+            }
+
+            // The first tombstone, or else the empty bucket ending the probe.
+            if free.is_none() {
+                free = group.match_empty_or_deleted().lowest_set_bit().map(|bit| (pos + bit) & mask);
+            }
+
+            scanned += Group::WIDTH;
+            if end < Group::WIDTH || scanned >= self.slots.len() {
+                return Err(free.expect("FastMap has no free bucket left!"));
+            }
+
+            pos = (pos + Group::WIDTH) & mask;
+        }
+    }
+
+    #[inline]
+    fn find_free(&self, mut ix: usize) -> usize {
+        let mask = self.slots.len() - 1;
+
+        loop {
+            if let Some(bit) = Group::load(&self.ctrl[ix..]).match_empty_or_deleted().lowest_set_bit() {
+                return (ix + bit) & mask;
+            }
+
+            ix = (ix + Group::WIDTH) & mask;
+        }
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for Swiss<K, V> {
+    fn clone(&self) -> Self {
+        let slots = self.slots.iter().zip(&self.ctrl).map(|(slot, &c)| {
+            if is_full(c) {
+                // SAFETY: the bucket is full.
+                MaybeUninit::new(unsafe { slot.assume_init_ref() }.clone())
+            } else {
+                MaybeUninit::uninit()
+            }
+        }).collect();
+
+        Swiss { ctrl: self.ctrl.clone(), slots }
+    }
+}
+
+impl<K, V> Drop for Swiss<K, V> {
+    fn drop(&mut self) {
+        if mem::needs_drop::<(K, V)>() {
+            self.drop_entries();
+        }
+    }
+}
+
+
+/// The 7 bit tag stored in the control byte of a full bucket.
+#[inline]
+fn h2(hash: u64) -> u8 {
+    (hash >> 57) as u8
+}
+
+#[inline]
+fn is_full(c: u8) -> bool {
+    c & 0x80 == 0
+}
+
+
+mod group {
+    /// Set bits of a group match, one per matching control byte.
+    pub struct BitMask(pub u64, pub u32);
+
+    impl BitMask {
+        #[inline]
+        pub fn lowest_set_bit(&self) -> Option<usize> {
+            if self.0 == 0 {
+                None
+            } else {
+                Some((self.0.trailing_zeros() >> self.1) as usize)
+            }
+        }
+    }
+
+    impl Iterator for BitMask {
+        type Item = usize;
+
+        #[inline]
+        fn next(&mut self) -> Option<usize> {
+            let bit = self.lowest_set_bit()?;
+            self.0 &= self.0 - 1;
+            Some(bit)
+        }
+    }
+
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2", not(feature = "portable")))]
+    pub use self::sse2::Group;
+
+    #[cfg(not(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2", not(feature = "portable"))))]
+    pub use self::portable::Group;
+
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2", not(feature = "portable")))]
+    mod sse2 {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86 as arch;
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64 as arch;
+
+        use super::BitMask;
+
+        /// 16 control bytes in an SSE2 register.
+        pub struct Group(arch::__m128i);
+
+        impl Group {
+            pub const WIDTH: usize = 16;
+
+            /// Loads the first 16 bytes of `ctrl`, unaligned.
+            #[inline]
+            pub fn load(ctrl: &[u8]) -> Group {
+                let bytes = &ctrl[..Group::WIDTH];
+                // SAFETY: `bytes` holds 16 bytes and the load doesn't need alignment.
+                unsafe { Group(arch::_mm_loadu_si128(bytes.as_ptr() as *const arch::__m128i)) }
+            }
+
+            #[inline]
+            pub fn match_byte(&self, byte: u8) -> BitMask {
+                // SAFETY: SSE2 is enabled at compile time.
+                unsafe {
+                    let cmp = arch::_mm_cmpeq_epi8(self.0, arch::_mm_set1_epi8(byte as i8));
+                    BitMask(arch::_mm_movemask_epi8(cmp) as u16 as u64, 0)
+                }
+            }
+
+            #[inline]
+            pub fn match_empty(&self) -> BitMask {
+                self.match_byte(super::super::EMPTY)
+            }
+
+            /// Empty and deleted are the only control bytes with the high bit set.
+            #[inline]
+            pub fn match_empty_or_deleted(&self) -> BitMask {
+                // SAFETY: SSE2 is enabled at compile time.
+                unsafe { BitMask(arch::_mm_movemask_epi8(self.0) as u16 as u64, 0) }
+            }
+        }
+    }
+
+    #[cfg(not(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2", not(feature = "portable"))))]
+    mod portable {
+        use super::BitMask;
+
+        const LSB: u64 = 0x0101_0101_0101_0101;
+        const MSB: u64 = 0x8080_8080_8080_8080;
+
+        /// 8 control bytes in a u64, matched a byte at a time with bit tricks.
+        pub struct Group(u64);
+
+        impl Group {
+            pub const WIDTH: usize = 8;
+
+            /// Loads the first 8 bytes of `ctrl`, little-endian so bit order follows byte order.
+            #[inline]
+            pub fn load(ctrl: &[u8]) -> Group {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(&ctrl[..Group::WIDTH]);
+                Group(u64::from_le_bytes(bytes))
+            }
+
+            /// May report a full byte right after a real match too, callers compare keys anyway.
+            /// Never reports an empty or deleted byte, as a tag never has the high bit set.
+            #[inline]
+            pub fn match_byte(&self, byte: u8) -> BitMask {
+                let cmp = self.0 ^ (LSB * byte as u64);
+                BitMask(cmp.wrapping_sub(LSB) & !cmp & MSB, 3)
+            }
+
+            /// Empty is the only control byte with both of the top two bits set.
+            #[inline]
+            pub fn match_empty(&self) -> BitMask {
+                BitMask(self.0 & (self.0 << 1) & MSB, 3)
+            }
+
+            /// Empty and deleted are the only control bytes with the high bit set.
+            #[inline]
+            pub fn match_empty_or_deleted(&self) -> BitMask {
+                BitMask(self.0 & MSB, 3)
+            }
+        }
+    }
+}
+
+
+// ***************** Iterators *********************
+
+// Control bytes paired with slots, an entry is there when the byte is full.
+type SwissBuckets<'a, I> = Zip<SliceIter<'a, u8>, I>;
+
+pub struct SwissIter<'a, K: 'a, V: 'a> {
+    outer: SwissBuckets<'a, SliceIter<'a, MaybeUninit<(u64, K, V)>>>,
+}
+
+impl<'a, K, V> Iterator for SwissIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            let (&c, slot) = self.outer.next()?;
+            if is_full(c) {
+                // SAFETY: the bucket is full.
+                let (_, ref k, ref v) = *unsafe { slot.assume_init_ref() };
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for SwissIter<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            let (&c, slot) = self.outer.next_back()?;
+            if is_full(c) {
+                // SAFETY: the bucket is full.
+                let (_, ref k, ref v) = *unsafe { slot.assume_init_ref() };
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> FusedIterator for SwissIter<'a, K, V> {}
+
+impl<'a, K, V> Clone for SwissIter<'a, K, V> {
+    fn clone(&self) -> Self {
+        SwissIter { outer: self.outer.clone() }
+    }
+}
+
+pub struct SwissIterMut<'a, K: 'a, V: 'a> {
+    outer: SwissBuckets<'a, SliceIterMut<'a, MaybeUninit<(u64, K, V)>>>,
+}
+
+impl<'a, K, V> Iterator for SwissIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        loop {
+            let (&c, slot) = self.outer.next()?;
+            if is_full(c) {
+                // SAFETY: the bucket is full.
+                let (_, ref k, ref mut v) = *unsafe { slot.assume_init_mut() };
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for SwissIterMut<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        loop {
+            let (&c, slot) = self.outer.next_back()?;
+            if is_full(c) {
+                // SAFETY: the bucket is full.
+                let (_, ref k, ref mut v) = *unsafe { slot.assume_init_mut() };
+                return Some((k, v));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> FusedIterator for SwissIterMut<'a, K, V> {}
+
+pub struct SwissIntoEntries<K, V> {
+    ctrl: VecIntoIter<u8>,
+    slots: VecIntoIter<MaybeUninit<(u64, K, V)>>,
+}
+
+impl<K, V> Iterator for SwissIntoEntries<K, V> {
+    type Item = (u64, K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(u64, K, V)> {
+        loop {
+            let (c, slot) = (self.ctrl.next()?, self.slots.next()?);
+            if is_full(c) {
+                // SAFETY: the bucket is full, and its control byte is consumed with the entry.
+                return Some(unsafe { slot.assume_init() });
+            }
+        }
+    }
+}
+
+impl<K, V> DoubleEndedIterator for SwissIntoEntries<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(u64, K, V)> {
+        loop {
+            let (c, slot) = (self.ctrl.next_back()?, self.slots.next_back()?);
+            if is_full(c) {
+                // SAFETY: the bucket is full, and its control byte is consumed with the entry.
+                return Some(unsafe { slot.assume_init() });
+            }
+        }
+    }
+}
+
+impl<K, V> FusedIterator for SwissIntoEntries<K, V> {}
+
+impl<K, V> Drop for SwissIntoEntries<K, V> {
+    /// Drops the entries that weren't taken, the arrays alone wouldn't.
+    fn drop(&mut self) {
+        if mem::needs_drop::<(K, V)>() {
+            self.by_ref().for_each(drop);
+        }
+    }
+}
@@ -3,7 +3,32 @@ extern crate rand;
 
 extern crate fastmap;
 
-use fastmap::FastMap;
+mod common;
+
+use common::IdentityHasher;
+use fastmap::{CheckPolicy, FastMap, FastMapConfig, TryReserveError};
+
+use std::hash::{BuildHasher, Hasher};
+
+// Sends every key to the last bucket of the table.
+#[derive(Default)]
+struct TailHasher;
+
+impl BuildHasher for TailHasher {
+    type Hasher = TailHasher;
+
+    fn build_hasher(&self) -> TailHasher {
+        TailHasher
+    }
+}
+
+impl Hasher for TailHasher {
+    fn write(&mut self, _: &[u8]) {}
+
+    fn finish(&self) -> u64 {
+        u64::MAX
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -30,36 +55,36 @@ mod tests {
     #[test]
     fn fastmap_get_insert_impl() {
         let count = 20_000;
-        let data = get_random_range(count as usize);
+        let data = get_random_range(count);
         let mut map: FastMap<u64, u64> = FastMap::new();
 
-        println!("");
+        println!();
         println!("Starting test");
 
         for s in data.iter() {
-            assert!(map.insert(*s, *s), "fastmap insert failed! ix: {:?}", s);
+            assert!(map.insert(*s, *s).is_none(), "fastmap insert failed! ix: {:?}", s);
         }
 
         assert_eq!(map.len(), count);
         assert!(map.assert_count());
 
         for s in data.iter() {
-            assert_eq!(*map.get(*s).unwrap(), *s, "fastmap get failed! key: {:?}", s);
+            assert_eq!(*map.get(s).unwrap(), *s, "fastmap get failed! key: {:?}", s);
         }
 
         assert_eq!(map.len(), count);
 
         for s in data.iter() {
-            assert!(map.contains_key(*s), "fastmap contains_key failed! key: {:?}", s);
+            assert!(map.contains_key(s), "fastmap contains_key failed! key: {:?}", s);
         }
 
         assert_eq!(map.len(), count);
 
         for s in data.iter() {
-            if let Some(val) = map.remove(*s) {
+            if let Some(val) = map.remove(s) {
                 assert_eq!(val, *s, "fastmap remove failed! key: {:?}", s);
             } else {
-                assert!(false, "Failed to remove value: {:?}", *s);
+                panic!("Failed to remove value: {:?}", *s);
             }
         }
 
@@ -72,23 +97,587 @@ mod tests {
         let mut map = FastMap::new();
 
         for i in 0..20_000 {
-            assert!(map.insert(i, format!("item: {:?}", i)), format!("Failed to insert key in map: {}", i));
+            assert!(map.insert(i, format!("item: {:?}", i)).is_none(), "Failed to insert key in map: {}", i);
         }
 
         for i in 0..20_000 {
-            if let Some(s) = map.get(i) {
+            if let Some(s) = map.get(&i) {
                 assert_eq!(*s, format!("item: {:?}", i));
             } else {
-                assert!(false, format!("Failed to retrive key in map: {}", i));
+                panic!("Failed to retrive key in map: {}", i);
+            }
+        }
+    }
+
+    #[test]
+    fn borrowed_lookup() {
+        let mut map: FastMap<String, u64> = FastMap::new();
+
+        for i in 0..1000 {
+            map.insert(format!("item: {:?}", i), i);
+        }
+
+        assert_eq!(*map.get("item: 21").unwrap(), 21);
+        assert!(map.contains_key("item: 999"));
+        assert!(!map.contains_key("item: 1000"));
+
+        *map.get_mut("item: 21").unwrap() += 1;
+        assert_eq!(map.remove("item: 21"), Some(22));
+        assert!(!map.contains_key("item: 21"));
+
+        let mut bytes: FastMap<Vec<u8>, u64> = FastMap::new();
+        bytes.insert(b"Eat my shorts".to_vec(), 42);
+        assert_eq!(*bytes.get(&b"Eat my shorts"[..]).unwrap(), 42);
+    }
+
+    #[test]
+    fn custom_hasher() {
+        use std::collections::hash_map::{DefaultHasher, RandomState};
+        use std::hash::BuildHasherDefault;
+
+        let count = 20_000;
+        let mut map: FastMap<u64, u64, RandomState> = FastMap::with_hasher(RandomState::new());
+        let mut other: FastMap<u64, u64, BuildHasherDefault<DefaultHasher>> = FastMap::with_capacity_and_hasher(count as usize, Default::default());
+
+        for i in 0..count {
+            assert!(map.insert(i, i).is_none());
+            assert!(other.insert(i, i).is_none());
+        }
+
+        for i in 0..count {
+            assert_eq!(*map.get(&i).unwrap(), i);
+            assert_eq!(other.remove(&i), Some(i));
+        }
+
+        assert_eq!(map.iter().count(), count as usize);
+        assert!(other.is_empty());
+    }
+
+    #[test]
+    fn seeded_hasher() {
+        use fastmap::BuildMurmur2_64a;
+        use std::hash::BuildHasher;
+
+        let a = BuildMurmur2_64a::with_seed(1);
+        let b = BuildMurmur2_64a::with_seed(2);
+        assert_ne!(a.hash_one("Eat my shorts"), b.hash_one("Eat my shorts"));
+        assert_eq!(a.hash_one("Eat my shorts"), BuildMurmur2_64a::with_seed(1).hash_one("Eat my shorts"));
+
+        let mut map: FastMap<u64, u64> = FastMap::with_hasher(a);
+        for i in 0..20_000 {
+            map.insert(i, i);
+        }
+
+        for i in 0..20_000 {
+            assert_eq!(*map.get(&i).unwrap(), i);
+        }
+    }
+
+    #[test]
+    fn build_hasher_is_stateless() {
+        use fastmap::{BuildMurmur2_64a, Murmur2_64a};
+        use std::collections::HashMap;
+        use std::hash::{BuildHasher, BuildHasherDefault, Hasher};
+
+        let builder = BuildMurmur2_64a::default();
+        let before = builder.hash_one(21u64);
+
+        let mut hasher = builder.build_hasher();
+        hasher.write(b"Eat my shorts");
+        assert_ne!(hasher.finish(), before);
+
+        assert_eq!(builder.hash_one(21u64), before);
+        assert_eq!(BuildHasherDefault::<Murmur2_64a>::default().hash_one(21u64), before);
+
+        let mut map: HashMap<u64, u64, BuildMurmur2_64a> = HashMap::default();
+        let mut other: HashMap<u64, u64, BuildHasherDefault<Murmur2_64a>> = HashMap::default();
+        for i in 0..1000 {
+            map.insert(i, i);
+            other.insert(i, i);
+        }
+        assert_eq!(map, other.into_iter().collect());
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn randomized_map() {
+        use fastmap::RandomMurmur;
+
+        let mut map = FastMap::new_randomized();
+        for i in 0..20_000 {
+            map.insert(i, i);
+        }
+
+        for i in 0..20_000 {
+            assert_eq!(*map.get(&i).unwrap(), i);
+        }
+
+        assert_ne!(RandomMurmur::new().seed(), RandomMurmur::new().seed());
+    }
+
+    #[test]
+    fn probe_wraps_at_tail() {
+        let count = 200;
+        let mut map: FastMap<u64, u64, TailHasher> = FastMap::with_hasher(TailHasher);
+
+        for i in 0..count {
+            assert!(map.insert(i, i).is_none());
+        }
+
+        for i in 0..count {
+            assert_eq!(*map.get(&i).unwrap(), i);
+        }
+
+        assert!(!map.contains_key(&count));
+
+        for i in (0..count).step_by(2) {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+
+        for i in 0..count {
+            assert_eq!(map.get(&i).is_some(), i % 2 == 1);
+            *map.entry(i).or_insert(0) += 1;
+        }
+
+        assert_eq!(map.len(), count as usize);
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn adversarial_tail_keys() {
+        use std::hash::BuildHasherDefault;
+
+        // Every key lands in the last bucket of any table up to 4096 buckets.
+        let keys: Vec<u64> = (0..1000).map(|i| (i << 12) | 0xfff).collect();
+        let mut map: FastMap<u64, u64, BuildHasherDefault<IdentityHasher>> = FastMap::with_capacity_and_hasher(16, Default::default());
+
+        for k in keys.iter() {
+            map.insert(*k, *k);
+        }
+
+        for k in keys.iter() {
+            assert_eq!(map.remove(k), Some(*k));
+        }
+
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn churn_keeps_probes_short() {
+        let size = 1000;
+        let data = get_random_range(200_000);
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for s in data[..size].iter() {
+            map.insert(*s, *s);
+        }
+
+        let mut longest = 0;
+
+        // Remove the oldest key and insert a new one, keeping the map at a steady size.
+        for (i, s) in data[size..].iter().enumerate() {
+            assert_eq!(map.remove(&data[i]), Some(data[i]));
+            assert!(map.insert(*s, *s).is_none());
+
+            if i % 1000 == 0 {
+                assert!((map.len() + map.tombstones()) * 100 / map.capacity() <= 70);
+                longest = longest.max(map.max_probe_length());
+            }
+        }
+
+        assert_eq!(map.len(), size);
+        assert!(map.assert_count());
+        assert!(map.capacity() <= 2048, "map grew to {} buckets", map.capacity());
+        assert!(longest < 100, "probe length grew to {}", longest);
+
+        for s in data[data.len() - size..].iter() {
+            assert_eq!(*map.get(s).unwrap(), *s);
+        }
+    }
+
+    #[test]
+    fn shrink_after_mass_remove() {
+        let data = get_random_range(10_000);
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+
+        for s in data[100..].iter() {
+            map.remove(s);
+        }
+
+        let peak = map.capacity();
+        map.shrink_to_fit();
+
+        assert!(map.capacity() < peak);
+        assert_eq!(map.capacity(), 256);
+        assert_eq!(map.tombstones(), 0);
+        assert!(map.assert_count());
+
+        for s in data[..100].iter() {
+            assert_eq!(*map.get(s).unwrap(), *s);
+        }
+        for s in data[100..].iter() {
+            assert!(map.get(s).is_none());
+        }
+    }
+
+    #[test]
+    fn shrink_to_keeps_min_capacity() {
+        let mut map: FastMap<u64, u64> = FastMap::with_capacity(4096);
+
+        for i in 0..10 {
+            map.insert(i, i);
+        }
+
+        map.shrink_to(1000);
+        assert_eq!(map.capacity(), 2048);
+
+        // Never grows, and never drops below what's in the map.
+        map.shrink_to(3000);
+        assert_eq!(map.capacity(), 2048);
+        map.shrink_to(0);
+        assert_eq!(map.capacity(), 16);
+
+        for i in 0..10 {
+            assert_eq!(*map.get(&i).unwrap(), i);
+        }
+    }
+
+    #[test]
+    fn auto_shrink() {
+        let data = get_random_range(10_000);
+        let mut map: FastMap<u64, u64> = FastMap::with_config(FastMapConfig::new().min_load_factor(0.2));
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+
+        let peak = map.capacity();
+
+        for (i, s) in data.iter().enumerate() {
+            assert_eq!(map.remove(s), Some(*s));
+
+            if i % 100 == 0 {
+                assert!(map.assert_count());
+            }
+        }
+
+        assert!(map.is_empty());
+        assert!(map.capacity() < peak);
+        assert!(map.capacity() <= 4, "map kept {} buckets", map.capacity());
+
+        // Off by default.
+        let mut map: FastMap<u64, u64> = FastMap::new();
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+        for s in data.iter() {
+            map.remove(s);
+        }
+        assert_eq!(map.capacity(), peak);
+    }
+
+    #[test]
+    fn reserve_then_insert() {
+        let data = get_random_range(10_000);
+        let mut map: FastMap<u64, u64> = FastMap::new();
+        map.insert(0, 0);
+
+        map.reserve(data.len());
+        let capacity = map.capacity();
+        assert_eq!(capacity, 16384);
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+
+        assert_eq!(map.capacity(), capacity);
+        assert!(map.assert_count());
+
+        // Already room, nothing changes.
+        map.reserve(100);
+        assert_eq!(map.capacity(), capacity);
+    }
+
+    #[test]
+    fn try_reserve_overflow() {
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+
+        let capacity = map.capacity();
+
+        assert_eq!(map.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(map.try_reserve(usize::MAX / 2), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(map.try_reserve(usize::MAX / 64), Err(TryReserveError::CapacityOverflow));
+
+        // Failed attempts leave the map as it was.
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.capacity(), capacity);
+        for i in 0..100 {
+            assert_eq!(*map.get(&i).unwrap(), i);
+        }
+
+        assert_eq!(map.try_reserve(1000), Ok(()));
+        assert!(map.capacity() > capacity);
+    }
+
+    #[test]
+    fn insert_fallible_reports_overflow() {
+        // Growing by 2^62 overflows on the first resize.
+        let config = FastMapConfig::new().capacity(8).growth_factor(1 << 62);
+        let mut map: FastMap<u64, u64> = FastMap::with_config(config);
+
+        let mut i = 0;
+        let err = loop {
+            match map.insert_fallible(i, i) {
+                Ok(None) => i += 1,
+                Ok(Some(_)) => panic!("Key {} was already present!", i),
+                Err(err) => break err,
+            }
+        };
+
+        assert_eq!(err, TryReserveError::CapacityOverflow);
+        assert_eq!(map.len(), i as usize);
+        assert_eq!(map.capacity(), 8);
+        assert!(!map.contains_key(&i));
+        assert!(map.assert_count());
+
+        // Replacing an existing key doesn't need to grow.
+        assert_eq!(map.insert_fallible(0, 42), Ok(Some(0)));
+        assert_eq!(map.get(&0), Some(&42));
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn reserve_overflow_panics() {
+        let mut map: FastMap<u64, u64> = FastMap::new();
+        map.reserve(usize::MAX);
+    }
+
+    #[test]
+    fn config_max_load_factor() {
+        let data = get_random_range(10_000);
+        let mut map: FastMap<u64, u64> = FastMap::with_config(FastMapConfig::new().max_load_factor(0.5));
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+            assert!(map.len() * 2 <= map.capacity());
+        }
+
+        assert_eq!(map.capacity(), 32768);
+        map.extend(data.iter().map(|s| (*s, *s)));
+        assert_eq!(map.capacity(), 32768);
+    }
+
+    #[test]
+    fn config_growth_factor() {
+        let config = FastMapConfig::new().capacity(16).growth_factor(4);
+        let mut map: FastMap<u64, u64> = FastMap::with_config(config);
+        let mut sizes = vec![map.capacity()];
+
+        for i in 0..10_000 {
+            map.insert(i, i);
+
+            if map.capacity() != *sizes.last().unwrap() {
+                sizes.push(map.capacity());
+            }
+        }
+
+        assert_eq!(sizes, vec![16, 64, 256, 1024, 4096, 16384]);
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn config_amortized_checks() {
+        let size = 1000;
+        let data = get_random_range(50_000);
+        let config = FastMapConfig::new().check_policy(CheckPolicy::Amortized(64)).min_load_factor(0.1);
+        let mut map: FastMap<u64, u64> = FastMap::with_config(config);
+
+        for s in data[..size].iter() {
+            map.insert(*s, *s);
+        }
+
+        for (i, s) in data[size..].iter().enumerate() {
+            assert_eq!(map.remove(&data[i]), Some(data[i]));
+            *map.entry(*s).or_insert(0) += *s;
+
+            // Mass removals shrink the table, the next inserts have to notice.
+            if i % 5000 == 0 {
+                for s in data[i + 1..i + 901].iter() {
+                    map.remove(s);
+                }
+                for s in data[i + 1..i + 901].iter() {
+                    map.insert(*s, *s);
+                }
+            }
+
+            assert!(map.len() + map.tombstones() < map.capacity());
+        }
+
+        assert_eq!(map.len(), size);
+        assert!(map.assert_count());
+
+        for s in data[data.len() - size..].iter() {
+            assert_eq!(*map.get(s).unwrap(), *s);
+        }
+    }
+
+    #[test]
+    fn incremental_resize() {
+        let data = get_random_range(20_000);
+        let config = FastMapConfig::new().incremental_resize(4);
+        let mut map: FastMap<u64, u64> = FastMap::with_config(config);
+        let mut resizes = 0;
+
+        for (i, s) in data.iter().enumerate() {
+            let resizing = map.is_resizing();
+            map.insert(*s, *s);
+
+            if map.is_resizing() && !resizing {
+                resizes += 1;
+            }
+
+            if map.is_resizing() && i % 50 == 0 {
+                // Both tables are consulted while the old one drains.
+                assert_eq!(map.iter().count(), map.len());
+                assert!(map.assert_count());
+
+                for s in data[..i + 1].iter().step_by(7) {
+                    assert_eq!(*map.get(s).unwrap(), *s);
+                }
+            }
+        }
+
+        assert!(resizes > 5, "only {} incremental resizes", resizes);
+
+        // Mix in removes, replaces and entries while a resize is running.
+        let mut map: FastMap<u64, u64> = FastMap::with_config(config);
+        for s in data[..10_000].iter() {
+            map.insert(*s, *s);
+        }
+        while !map.is_resizing() {
+            let s = data[map.len()];
+            map.insert(s, s);
+        }
+
+        let len = map.len();
+        for s in data[..20].iter() {
+            assert_eq!(map.remove(s), Some(*s));
+        }
+        for s in data[20..40].iter() {
+            assert_eq!(map.insert(*s, s + 1), Some(*s));
+        }
+        for s in data[40..60].iter() {
+            *map.entry(*s).or_insert(0) += 1;
+        }
+        assert!(map.is_resizing());
+
+        assert_eq!(map.len(), len - 20);
+        assert!(map.assert_count());
+        for s in data[..20].iter() {
+            assert!(map.get(s).is_none());
+        }
+        for s in data[20..60].iter() {
+            assert_eq!(*map.get(s).unwrap(), s + 1);
+        }
+        for s in data[60..len].iter() {
+            assert_eq!(*map.get(s).unwrap(), *s);
+        }
+
+        while map.is_resizing() {
+            map.remove(&u64::MAX);
+        }
+        assert_eq!(map.len(), len - 20);
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn extend_map() {
+        let data = get_random_range(1000);
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        map.extend(data.iter().map(|s| (*s, *s)));
+        assert_eq!(map.capacity(), 2048);
+        map.extend(data.iter().map(|s| (*s, s + 1)));
+
+        assert_eq!(map.len(), data.len());
+        for s in data.iter() {
+            assert_eq!(*map.get(s).unwrap(), s + 1);
+        }
+    }
+
+    #[test]
+    fn incremental_resize_finishes_in_time() {
+        let data = get_random_range(50_000);
+
+        for &(load, growth, check) in &[(0.1, 2, 1), (0.3, 2, 1), (0.7, 2, 1), (0.9, 2, 1), (0.1, 4, 1), (0.7, 8, 1), (0.1, 2, 64), (0.7, 2, 64)] {
+            // A step of 1 is raised to what the load and growth factors need.
+            let config = FastMapConfig::new()
+                .max_load_factor(load)
+                .growth_factor(growth)
+                .check_policy(if check == 1 { CheckPolicy::EveryInsert } else { CheckPolicy::Amortized(check) })
+                .incremental_resize(1);
+            let mut map: FastMap<u64, u64> = FastMap::with_config(config);
+
+            for s in data.iter() {
+                let resizing = map.is_resizing();
+                let capacity = map.capacity();
+                map.insert(*s, *s);
+
+                // Growing again mid resize would have to move the rest in one go.
+                assert!(!resizing || map.capacity() == capacity,
+                        "load: {}, growth: {}, check: {}, len: {}", load, growth, check, map.len());
             }
+
+            assert!(map.assert_count());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Growth factor must be a power of 2")]
+    fn config_rejects_growth_factor() {
+        FastMapConfig::new().growth_factor(3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Min load factor must be at least 0 and below half")]
+    fn config_rejects_min_load_factor() {
+        FastMapConfig::new().max_load_factor(0.5).min_load_factor(0.25);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max load factor must be above twice the min load factor")]
+    fn config_rejects_max_below_min_load_factor() {
+        FastMapConfig::new().min_load_factor(0.2).max_load_factor(0.3);
+    }
+
+    #[test]
+    fn config_clamps_amortized_interval() {
+        let config = FastMapConfig::new().check_policy(CheckPolicy::Amortized(usize::MAX));
+        let mut map: FastMap<u64, u64> = FastMap::with_config(config);
+
+        for i in 0..10_000 {
+            map.insert(i, i);
+            assert!(map.len() < map.capacity());
         }
+
+        // Never grows more than one step ahead.
+        assert!(map.capacity() <= 4 * 10_000);
+        assert!(map.assert_count());
     }
 
     #[test]
     fn single_add_get() {
         let mut map: FastMap<u64, u64> = FastMap::new();
         map.insert(21, 42);
-        let val = map.get(21);
+        let val = map.get(&21);
         assert!(val.is_some());
         assert_eq!(*val.unwrap(), 42);
     }
@@ -151,11 +740,98 @@ mod tests {
         }
 
         for n in 0..count {
-            assert_eq!(n+1, *map.get(n).expect("Failed to get number!"));
+            assert_eq!(n+1, *map.get(&n).expect("Failed to get number!"));
         }
 
     }
 
+    #[test]
+    fn insert_replace() {
+        let count = 20_000;
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for i in 0..count {
+            assert_eq!(map.insert(i, i), None);
+        }
+
+        for i in 0..count {
+            assert_eq!(map.insert(i, i + 1), Some(i));
+        }
+
+        assert_eq!(map.len(), count as usize);
+        assert!(map.assert_count());
+
+        for i in 0..count {
+            assert_eq!(*map.get(&i).unwrap(), i + 1);
+        }
+    }
+
+    #[test]
+    fn try_insert_occupied() {
+        let mut map: FastMap<u64, String> = FastMap::new();
+
+        *map.try_insert(21, "Eat my shorts".to_string()).unwrap() += "!";
+
+        {
+            let err = map.try_insert(21, "Don't have a cow".to_string()).unwrap_err();
+            assert_eq!(*err.entry.key(), 21);
+            assert_eq!(err.entry.get(), "Eat my shorts!");
+            assert_eq!(err.value, "Don't have a cow");
+        }
+
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&21).unwrap(), "Eat my shorts!");
+    }
+
+    #[test]
+    fn entry_count() {
+        let count = 20_000;
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for i in 0..count {
+            *map.entry(i % 1000).or_insert(0) += 1;
+        }
+
+        assert_eq!(map.len(), 1000);
+        assert!(map.assert_count());
+
+        for i in 0..1000 {
+            assert_eq!(*map.get(&i).unwrap(), count / 1000);
+        }
+    }
+
+    #[test]
+    fn entry_occupied_vacant() {
+        use fastmap::Entry;
+
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        match map.entry(21) {
+            Entry::Vacant(entry) => {
+                assert_eq!(*entry.key(), 21);
+                *entry.insert(42) += 1;
+            }
+            Entry::Occupied(_) => panic!("Entry should be vacant!"),
+        }
+
+        match map.entry(21) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(*entry.key(), 21);
+                assert_eq!(*entry.get(), 43);
+                assert_eq!(entry.insert(1), 43);
+                assert_eq!(entry.remove_entry(), (21, 1));
+            }
+            Entry::Vacant(_) => panic!("Entry should be occupied!"),
+        }
+
+        assert!(map.is_empty());
+        assert!(map.assert_count());
+
+        *map.entry(21).and_modify(|v| *v = 0).or_default() += 5;
+        *map.entry(21).and_modify(|v| *v *= 2).or_default() += 1;
+        assert_eq!(*map.get(&21).unwrap(), 11);
+    }
+
     #[test]
     fn map_iter_empty() {
         let mut map: FastMap<u64, u64> = FastMap::new();
@@ -175,4 +851,301 @@ mod tests {
             *kv.1 += 1;
         }
     }
+
+    #[test]
+    fn into_iter_owned() {
+        let data = get_random_range(1000);
+        let mut map: FastMap<u64, String> = FastMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, s.to_string());
+        }
+
+        for (_, v) in &mut map {
+            v.push('!');
+        }
+        assert_eq!((&map).into_iter().count(), data.len());
+
+        let mut pairs: Vec<(u64, String)> = map.into_iter().collect();
+        pairs.sort();
+        for (s, (k, v)) in data.iter().zip(pairs.iter()) {
+            assert_eq!(k, s);
+            assert_eq!(*v, format!("{}!", s));
+        }
+    }
+
+    #[test]
+    fn drain_keeps_capacity() {
+        let data = get_random_range(1000);
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+        let capacity = map.capacity();
+
+        let mut drained: Vec<u64> = map.drain().map(|(k, _)| k).collect();
+        drained.sort();
+        assert_eq!(drained, data);
+        assert!(map.is_empty());
+        assert_eq!(map.tombstones(), 0);
+        assert_eq!(map.capacity(), capacity);
+
+        // Dropping a partly used drain still empties the map.
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+        assert_eq!(map.drain().take(10).count(), 10);
+        assert!(map.is_empty());
+        assert!(map.assert_count());
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    fn retain_fixes_counts() {
+        let data = get_random_range(10_000);
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+        let capacity = map.capacity();
+
+        map.retain(|k, v| {
+            *v += 1;
+            k % 2 == 0
+        });
+
+        let kept = data.iter().filter(|s| *s % 2 == 0).count();
+        assert_eq!(map.len(), kept);
+        assert_eq!(map.tombstones(), data.len() - kept);
+        assert_eq!(map.capacity(), capacity);
+        assert!(map.assert_count());
+        for s in data.iter() {
+            assert_eq!(map.get(s).cloned(), if s % 2 == 0 { Some(s + 1) } else { None });
+        }
+    }
+
+    #[test]
+    fn retain_shrinks_once_done() {
+        let mut map: FastMap<u64, u64> = FastMap::with_config(FastMapConfig::new().min_load_factor(0.1));
+
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        let peak = map.capacity();
+
+        map.retain(|k, _| *k < 10);
+        assert_eq!(map.len(), 10);
+        assert!(map.capacity() < peak);
+        for i in 0..10 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn extract_if_removes_matches() {
+        let data = get_random_range(10_000);
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+
+        let mut odd: Vec<u64> = map.extract_if(|k, _| k % 2 == 1).map(|(k, _)| k).collect();
+        odd.sort();
+        let expected: Vec<u64> = data.iter().cloned().filter(|s| s % 2 == 1).collect();
+        assert_eq!(odd, expected);
+        assert_eq!(map.len(), data.len() - odd.len());
+        assert!(map.assert_count());
+
+        // Stopping early keeps everything not yet visited.
+        let len = map.len();
+        assert_eq!(map.extract_if(|_, _| true).take(100).count(), 100);
+        assert_eq!(map.len(), len - 100);
+        assert_eq!(map.iter().count(), len - 100);
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn remove_while_resizing() {
+        let data = get_random_range(20_000);
+        let config = FastMapConfig::new().incremental_resize(4);
+        let mut map: FastMap<u64, u64> = FastMap::with_config(config);
+
+        for s in data[..10_000].iter() {
+            map.insert(*s, *s);
+        }
+        while !map.is_resizing() {
+            let s = data[map.len()];
+            map.insert(s, s);
+        }
+        let len = map.len();
+
+        map.retain(|k, _| k % 3 != 0);
+        let removed: Vec<(u64, u64)> = map.extract_if(|k, _| k % 3 == 1).collect();
+
+        assert_eq!(map.len() + removed.len(), data[..len].iter().filter(|s| *s % 3 != 0).count());
+        assert!(map.assert_count());
+        for s in data[..len].iter() {
+            assert_eq!(map.contains_key(s), s % 3 == 2);
+        }
+
+        let left = data[..len].iter().filter(|s| *s % 3 == 2).count();
+        assert_eq!(map.drain().count(), left);
+        assert!(!map.is_resizing());
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn iter_exact_size() {
+        let data = get_random_range(1000);
+        let mut map: FastMap<u64, u64> = FastMap::with_capacity(1 << 16);
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+
+        let mut iter = map.iter();
+        assert_eq!(iter.len(), data.len());
+        assert_eq!(iter.size_hint(), (data.len(), Some(data.len())));
+        iter.next();
+        iter.next_back();
+        assert_eq!(iter.len(), data.len() - 2);
+        assert_eq!(iter.clone().count(), data.len() - 2);
+
+        assert_eq!(map.keys().len(), data.len());
+        assert_eq!(map.values().len(), data.len());
+        assert_eq!(map.iter_mut().len(), data.len());
+        assert_eq!(map.values_mut().len(), data.len());
+        assert_eq!(map.into_iter().len(), data.len());
+    }
+
+    #[test]
+    fn iter_double_ended() {
+        let data = get_random_range(1000);
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+
+        let forward: Vec<u64> = map.keys().cloned().collect();
+        let mut backward: Vec<u64> = map.keys().rev().cloned().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        // Meeting in the middle yields every entry once.
+        let mut iter = map.iter();
+        let mut seen = Vec::new();
+        while let Some((k, _)) = iter.next() {
+            seen.push(*k);
+            if let Some((k, _)) = iter.next_back() {
+                seen.push(*k);
+            }
+        }
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        seen.sort();
+        assert_eq!(seen, data);
+    }
+
+    #[test]
+    fn values_mut_and_into_parts() {
+        let data = get_random_range(1000);
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+
+        for v in map.values_mut() {
+            *v = v.wrapping_add(1);
+        }
+        for s in data.iter() {
+            assert_eq!(*map.get(s).unwrap(), s.wrapping_add(1));
+        }
+
+        let mut keys: Vec<u64> = map.into_keys().collect();
+        keys.sort();
+        assert_eq!(keys, data);
+
+        let mut map: FastMap<u64, u64> = FastMap::new();
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+        let mut values: Vec<u64> = map.into_values().rev().collect();
+        values.sort();
+        assert_eq!(values, data);
+    }
+
+    #[test]
+    fn clone_keeps_layout() {
+        let data = get_random_range(1000);
+        let mut map: FastMap<u64, String> = FastMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, s.to_string());
+        }
+        for s in data.iter().step_by(3) {
+            map.remove(s);
+        }
+
+        let copy = map.clone();
+        assert_eq!(copy, map);
+        assert_eq!(copy.capacity(), map.capacity());
+        assert_eq!(copy.tombstones(), map.tombstones());
+        assert!(copy.iter().eq(map.iter()));
+        assert!(copy.assert_count());
+    }
+
+    #[test]
+    fn eq_ignores_bucket_order() {
+        let data = get_random_range(1000);
+
+        let a: FastMap<u64, u64> = data.iter().map(|s| (*s, *s)).collect();
+        let mut b: FastMap<u64, u64> = FastMap::with_capacity(1 << 14);
+        for s in data.iter().rev() {
+            b.insert(*s, *s);
+        }
+        assert_eq!(a, b);
+
+        b.insert(data[0], 0);
+        assert!(a != b);
+        b.remove(&data[0]);
+        assert!(a != b);
+        assert_eq!(FastMap::<u64, u64>::default(), FastMap::new());
+    }
+
+    #[test]
+    fn debug_and_index() {
+        let map: FastMap<u64, &str> = FastMap::from([(21, "Eat my shorts")]);
+
+        assert_eq!(format!("{:?}", map), "{21: \"Eat my shorts\"}");
+        assert_eq!(map[&21], "Eat my shorts");
+
+        let map: FastMap<String, u64> = FastMap::from([("Bart".to_string(), 10)]);
+        assert_eq!(map["Bart"], 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "key not found in FastMap")]
+    fn index_missing_key() {
+        let map: FastMap<u64, u64> = FastMap::new();
+        assert_eq!(map[&21], 42);
+    }
+
+    #[test]
+    fn extend_by_reference() {
+        let data = get_random_range(1000);
+        let source: Vec<(u64, u64)> = data.iter().map(|s| (*s, *s)).collect();
+
+        let mut map: FastMap<u64, u64> = FastMap::new();
+        map.extend(source.iter().map(|(k, v)| (k, v)));
+
+        assert_eq!(map.len(), data.len());
+        for s in data.iter() {
+            assert_eq!(map[s], *s);
+        }
+    }
 }
@@ -0,0 +1,31 @@
+use std::hash::{BuildHasher, Hasher};
+
+// Hashes integers to themselves, so keys can be aimed at chosen buckets.
+#[derive(Clone, Copy, Default)]
+pub struct IdentityHasher(pub u64);
+
+impl BuildHasher for IdentityHasher {
+    type Hasher = IdentityHasher;
+
+    fn build_hasher(&self) -> IdentityHasher {
+        IdentityHasher(0)
+    }
+}
+
+impl Hasher for IdentityHasher {
+    // Only u64 keys hash to themselves, anything else gets a plain byte fold so a test
+    // aiming it at a bucket fails its own assertions instead of panicking in here.
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = self.0.rotate_left(8) ^ b as u64;
+        }
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.0 = i;
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
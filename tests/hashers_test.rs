@@ -0,0 +1,130 @@
+extern crate fastmap;
+
+use fastmap::FastMap;
+use fastmap::hashers::{murmur3_x64_128, xxhash64};
+use fastmap::hashers::{BuildFibonacciHasher, BuildMurmur3_x64_128, BuildXxHash64};
+use fastmap::hashers::{Murmur3_x64_128, XxHash64};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::{BuildHasher, Hash, Hasher};
+
+    const FOX: &[u8] = b"The quick brown fox jumps over the lazy dog";
+    const LONG: &[u8] = b"0123456789abcdef0123456789abcdef0123456789";
+
+    // Reference MurmurHash3 x64_128, (key, seed, h1, h2).
+    const MURMUR3: &[(&[u8], u64, u64, u64)] = &[
+        (b"", 0, 0x0000000000000000, 0x0000000000000000),
+        (b"", 42, 0xf02aa77dfa1b8523, 0xd1016610da11cbb9),
+        (b"a", 0, 0x85555565f6597889, 0xe6b53a48510e895a),
+        (b"abc", 42, 0x0d85089fb3cff7d6, 0x7510712b42353d30),
+        (b"foo", 0, 0xe271865701f54561, 0x7eaf87e42bba7d87),
+        (b"Eat my shorts", 0, 0x678cfabb7351c584, 0x77824cc11f5f9dce),
+        (FOX, 0, 0xe34bbc7bbc071b6c, 0x7a433ca9c49a9347),
+        (FOX, 42, 0x740dcf93fe0bd5d7, 0xc4546cf4ec705c8f),
+        (LONG, 0, 0xb373b994805cb22f, 0xe5acef93d582f593),
+    ];
+
+    // Reference xxHash64, (key, seed, hash).
+    const XXHASH64: &[(&[u8], u64, u64)] = &[
+        (b"", 0, 0xef46db3751d8e999),
+        (b"", 42, 0x98b1582b0977e704),
+        (b"a", 0, 0xd24ec4f1a98c6e5b),
+        (b"abc", 0, 0x44bc2cf5ad770999),
+        (b"abc", 42, 0x13c1d910702770e6),
+        (b"Eat my shorts", 0, 0xc4d37dd4243e4568),
+        (FOX, 0, 0x0b242d361fda71bc),
+        (FOX, 42, 0xaa9f288a8baa3d3f),
+        (LONG, 0, 0xa76190c3acf08a1c),
+        (LONG, 42, 0xec3fafab8cf27c18),
+    ];
+
+    #[test]
+    fn murmur3_known_answers() {
+        for &(key, seed, h1, h2) in MURMUR3 {
+            assert_eq!(murmur3_x64_128(key, seed), (h1, h2), "key: {:?}, seed: {}", key, seed);
+
+            let mut hasher = BuildMurmur3_x64_128::with_seed(seed).build_hasher();
+            hasher.write(key);
+            assert_eq!(hasher.finish(), h1);
+        }
+    }
+
+    #[test]
+    fn xxhash64_known_answers() {
+        for &(key, seed, hash) in XXHASH64 {
+            assert_eq!(xxhash64(key, seed), hash, "key: {:?}, seed: {}", key, seed);
+
+            let mut hasher = BuildXxHash64::with_seed(seed).build_hasher();
+            hasher.write(key);
+            assert_eq!(hasher.finish(), hash);
+        }
+    }
+
+    #[test]
+    fn fibonacci_known_answers() {
+        // ((seed ^ k) * 0x9e3779b97f4a7c15).swap_bytes(), worked out by hand.
+        let build = BuildFibonacciHasher::new();
+        assert_eq!(build.hash_one(0u64), 0);
+        assert_eq!(build.hash_one(1u64), 0x157c4a7fb979379e);
+        assert_eq!(build.hash_one(2u64), 0x2af894fe72f36e3c);
+        assert_eq!(build.hash_one(42u64), 0x725b38e26ef819f5);
+        assert_eq!(build.hash_one(42u32), 0x725b38e26ef819f5);
+        assert_eq!(build.hash_one(u64::MAX), 0xeb83b5804686c861);
+        assert_eq!(BuildFibonacciHasher::with_seed(42).hash_one(1u64), 0x87d7826128725193);
+    }
+
+    #[test]
+    fn split_writes_match_one_shot() {
+        let data: Vec<u8> = (0..100u32).map(|i| (i * 7) as u8).collect();
+
+        for len in 0..data.len() {
+            for split in 0..len + 1 {
+                let (a, b) = data[..len].split_at(split);
+
+                let mut murmur = Murmur3_x64_128::with_seed(42);
+                murmur.write(a);
+                murmur.write(b);
+                assert_eq!(murmur.finish128(), murmur3_x64_128(&data[..len], 42));
+
+                let mut xx = XxHash64::with_seed(42);
+                xx.write(a);
+                xx.write(b);
+                assert_eq!(xx.finish(), xxhash64(&data[..len], 42));
+            }
+        }
+    }
+
+    #[test]
+    fn integer_writes_are_little_endian() {
+        let mut murmur = Murmur3_x64_128::new();
+        0x0807060504030201u64.hash(&mut murmur);
+        assert_eq!(murmur.finish(), murmur3_x64_128(&[1, 2, 3, 4, 5, 6, 7, 8], 0).0);
+
+        let mut xx = XxHash64::new();
+        0x04030201u32.hash(&mut xx);
+        assert_eq!(xx.finish(), xxhash64(&[1, 2, 3, 4], 0));
+    }
+
+    #[test]
+    fn maps_with_hashers() {
+        let count = 20_000;
+
+        let mut ints: FastMap<u64, u64, _> = FastMap::with_hasher(BuildFibonacciHasher::new());
+        let mut xx: FastMap<String, u64, _> = FastMap::with_hasher(BuildXxHash64::new());
+        let mut murmur: FastMap<String, u64, _> = FastMap::with_hasher(BuildMurmur3_x64_128::new());
+
+        for i in 0..count {
+            ints.insert(i, i);
+            xx.insert(format!("item: {:?}", i), i);
+            murmur.insert(format!("item: {:?}", i), i);
+        }
+
+        for i in 0..count {
+            assert_eq!(*ints.get(&i).unwrap(), i);
+            assert_eq!(*xx.get(&format!("item: {:?}", i)).unwrap(), i);
+            assert_eq!(*murmur.get(&format!("item: {:?}", i)).unwrap(), i);
+        }
+    }
+}
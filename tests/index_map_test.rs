@@ -0,0 +1,231 @@
+extern crate rand;
+
+extern crate fastmap;
+
+use fastmap::{CheckPolicy, FastIndexMap, FastMapConfig};
+
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_insertion_order() {
+        let data = get_random_range(10_000);
+        let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+
+        // Insert in reverse so the order differs from both bucket and key order.
+        for s in data.iter().rev() {
+            assert_eq!(map.insert(*s, *s), None);
+        }
+        for s in data.iter() {
+            assert_eq!(map.insert(*s, s + 1), Some(*s));
+        }
+
+        let keys: Vec<u64> = map.keys().cloned().collect();
+        let expected: Vec<u64> = data.iter().rev().cloned().collect();
+        assert_eq!(keys, expected);
+
+        for (i, s) in expected.iter().enumerate() {
+            assert_eq!(map.get_index_of(s), Some(i));
+            assert_eq!(map.get_index(i), Some((s, &(s + 1))));
+        }
+        assert_eq!(map.get_index(map.len()), None);
+    }
+
+    #[test]
+    fn matches_std_map() {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+        let mut model = HashMap::new();
+
+        for i in 0..100_000u64 {
+            let key = rng.gen_range(0, 2000);
+
+            match rng.gen_range(0, 4) {
+                0 => assert_eq!(map.swap_remove(&key), model.remove(&key)),
+                1 => assert_eq!(map.shift_remove(&key), model.remove(&key)),
+                _ => assert_eq!(map.insert(key, i), model.insert(key, i)),
+            }
+        }
+
+        assert_eq!(map.len(), model.len());
+        assert_eq!(map.iter().count(), model.len());
+        for (k, v) in model.iter() {
+            assert_eq!(map.get(k), Some(v));
+        }
+        for i in 0..map.len() {
+            let (k, _) = map.get_index(i).unwrap();
+            assert_eq!(map.get_index_of(k), Some(i));
+        }
+    }
+
+    #[test]
+    fn swap_and_shift_remove() {
+        let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+        for i in 0..10 {
+            map.insert(i, i);
+        }
+
+        assert_eq!(map.swap_remove(&2), Some(2));
+        assert_eq!(map.swap_remove(&2), None);
+        assert_eq!(map.get_index(2), Some((&9, &9)));
+        assert_eq!(map.get_index_of(&9), Some(2));
+
+        assert_eq!(map.shift_remove(&0), Some(0));
+        assert_eq!(map.shift_remove(&0), None);
+        assert_eq!(map.keys().cloned().collect::<Vec<_>>(), [1, 9, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(map.get_index_of(&8), Some(7));
+
+        // Removing the last entry moves nothing.
+        assert_eq!(map.swap_remove(&8), Some(8));
+        assert_eq!(map.keys().cloned().collect::<Vec<_>>(), [1, 9, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn sort_keys() {
+        let data = get_random_range(1000);
+        let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+
+        for s in data.iter().rev() {
+            map.insert(*s, *s);
+        }
+
+        map.sort_keys();
+        assert_eq!(map.keys().cloned().collect::<Vec<_>>(), data);
+
+        for (i, s) in data.iter().enumerate() {
+            assert_eq!(map.get_index_of(s), Some(i));
+            assert_eq!(map.get(s), Some(s));
+        }
+    }
+
+    #[test]
+    fn iterators() {
+        let data = get_random_range(1000);
+        let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+
+        for (_, v) in map.iter_mut() {
+            *v += 1;
+        }
+
+        let values: Vec<u64> = map.values().map(|v| v - 1).collect();
+        assert_eq!(values, data);
+
+        assert_eq!(map.iter().len(), data.len());
+        assert_eq!(map.iter_mut().len(), data.len());
+        assert_eq!(map.keys().rev().cloned().collect::<Vec<_>>(), data.iter().rev().cloned().collect::<Vec<_>>());
+        assert_eq!(map.values().next_back(), Some(&(data[data.len() - 1] + 1)));
+        assert_eq!(map.iter_mut().next_back().map(|(k, _)| *k), data.last().cloned());
+
+        let mut keys = map.keys();
+        keys.next();
+        assert!(keys.clone().eq(data[1..].iter()));
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.iter().count(), 0);
+        assert_eq!(map.get(&data[0]), None);
+    }
+
+
+    #[test]
+    fn std_traits() {
+        let data = get_random_range(1000);
+
+        // Collected in reverse, iteration follows the input rather than the keys.
+        let map: FastIndexMap<u64, u64> = data.iter().rev().map(|&s| (s, s / 2)).collect();
+        assert_eq!(map.keys().cloned().collect::<Vec<_>>(), data.iter().rev().cloned().collect::<Vec<_>>());
+
+        let mut other: FastIndexMap<u64, u64> = FastIndexMap::default();
+        other.extend(&map);
+        other.extend(vec![(data[0], 0)]);
+        assert_eq!(other.len(), map.len());
+        assert_eq!(other.get_index_of(&data[0]), Some(data.len() - 1));
+        assert_eq!(other.get(&data[0]), Some(&0));
+
+        for (_, v) in &mut other {
+            *v += 1;
+        }
+        for ((k, v), (ok, ov)) in (&map).into_iter().zip(&other) {
+            assert_eq!(k, ok);
+            assert_eq!(*ov, if *k == data[0] { 1 } else { v + 1 });
+        }
+
+        let owned: Vec<(u64, u64)> = map.into_iter().rev().collect();
+        assert_eq!(owned, data.iter().map(|&s| (s, s / 2)).collect::<Vec<_>>());
+
+        let small = FastIndexMap::<&str, u64>::from([("b", 1), ("a", 2)]);
+        assert_eq!(small.into_iter().collect::<Vec<_>>(), [("b", 1), ("a", 2)]);
+    }
+
+
+    #[test]
+    fn follows_config() {
+        let config = FastMapConfig::new().max_load_factor(0.5).growth_factor(4).min_load_factor(0.1);
+        let mut map: FastIndexMap<u64, u64> = FastIndexMap::with_config(config);
+
+        for i in 0..1000 {
+            map.insert(i, i);
+            assert!(map.len() * 2 <= map.capacity());
+        }
+        assert_eq!(map.capacity(), 4096);
+
+        for i in 0..990 {
+            map.swap_remove(&i);
+        }
+        assert!(map.capacity() < 4096);
+
+        for i in 990..1000 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+
+        let mut map: FastIndexMap<u64, u64> = FastIndexMap::with_config(FastMapConfig::new().check_policy(CheckPolicy::Amortized(64)));
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.len(), 10_000);
+        assert!(map.load_rate() <= 70.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "FastIndexMap doesn't resize incrementally")]
+    fn rejects_incremental_resize() {
+        let _: FastIndexMap<u64, u64> = FastIndexMap::with_config(FastMapConfig::new().incremental_resize(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn reserve_overflow_panics() {
+        let mut map: FastIndexMap<u64, u64> = FastIndexMap::new();
+        map.reserve(usize::MAX / 2);
+    }
+
+
+    fn get_random_range(count: usize) -> Vec<u64> {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let mut vec = Vec::new();
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        for _ in 0..count {
+            vec.push(rng.gen::<u64>());
+        }
+
+        vec.sort();
+        vec.dedup();
+
+        vec
+    }
+}
@@ -0,0 +1,157 @@
+extern crate fastmap;
+
+use fastmap::{murmur_hash64a, BuildMurmur2_64a, Murmur2_64a};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::{BuildHasher, Hash, Hasher};
+
+    fn stream(parts: &[&[u8]], seed: u64) -> u64 {
+        let mut hasher = Murmur2_64a::with_seed(seed);
+
+        for part in parts {
+            hasher.write(part);
+        }
+
+        hasher.finish()
+    }
+
+    // Reference MurmurHash64A, (key, seed, hash).
+    const KNOWN: &[(&[u8], u64, u64)] = &[
+        (b"", 0, 0x0000000000000000),
+        (b"", 1, 0xc6a4a7935bd064dc),
+        (b"a", 0, 0x071717d2d36b6b11),
+        (b"ab", 0, 0x62be85b2fe53d1f8),
+        (b"abc", 0, 0x9cc9c33498a95efb),
+        (b"abcd", 0, 0xec1044c45cc5097a),
+        (b"abcde", 0, 0x1182974836d6dbb7),
+        (b"abcdef", 0, 0xb78e3425fc996779),
+        (b"abcdefg", 0, 0x241aa52b0a62005d),
+        (b"abcdefgh", 0, 0xafdb0257ff41aa98),
+        (b"abcdefghi", 0, 0xc9b9d84356146ac2),
+        (b"Eat my shorts", 0, 0x2f8410654c884762),
+        (b"The quick brown fox jumps over the lazy dog", 0, 0x5589ca33042a861b),
+        (b"The quick brown fox jumps over the lazy dog", 0x9747b28c, 0x029a7747a564bd84),
+        (b"hello", 42, 0xd417125ccb971887),
+    ];
+
+    // Reference MurmurHash64A of input past the 32 bytes Murmur2_64a hashes in one go.
+    const KNOWN_LONG: &[(&[u8], u64, u64)] = &[
+        (b"abcdefghijklmnopqrstuvwxyz0123456789", 0, 0x48b3bc0410be2f9a),
+        (b"abcdefghijklmnopqrstuvwxyz0123456789", 0x9747b28c, 0x30b34341c2f6c154),
+        (b"The quick brown fox jumps over the lazy dog.", 0, 0x8adb11747aa7b565),
+        (b"The quick brown fox jumps over the lazy dog.", 0x9747b28c, 0x15020e51a02fd7fc),
+        (b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789+/", 0, 0x5e49e75739e8632c),
+        (b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789+/", 0x9747b28c, 0x28ce2593ed8f3e9c),
+        (b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore", 0, 0xf2a975d5040d7a2d),
+        (b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore", 0x9747b28c, 0x24e6e4f66d97eb91),
+        (&[0; 100], 0, 0x2ce15cdcb12dcf55),
+    ];
+
+    #[test]
+    fn known_answers() {
+        for &(key, seed, hash) in KNOWN {
+            assert_eq!(murmur_hash64a(key, seed), hash, "key: {:?}, seed: {}", key, seed);
+        }
+    }
+
+    #[test]
+    fn long_known_answers() {
+        for &(key, seed, hash) in KNOWN_LONG {
+            assert_eq!(murmur_hash64a(key, seed), hash, "key: {:?}, seed: {}", key, seed);
+
+            // The hasher streams these, so it only has to agree with itself.
+            let chunks: Vec<&[u8]> = key.chunks(5).collect();
+            assert_eq!(stream(&chunks, seed), stream(&[key], seed), "key: {:?}, seed: {}, chunks of 5", key, seed);
+        }
+    }
+
+    #[test]
+    fn unaligned_input() {
+        let data: Vec<u8> = (0..64u8).collect();
+        let mut shifted = vec![0u8; 1];
+        shifted.extend_from_slice(&data);
+
+        for start in 0..8 {
+            let copy = data[start..].to_vec();
+            assert_eq!(murmur_hash64a(&shifted[start + 1..], 0), murmur_hash64a(&copy, 0));
+        }
+    }
+
+    #[test]
+    fn split_writes_match_one_shot() {
+        assert_eq!(stream(&[b"ab", b"c"], 0), murmur_hash64a(b"abc", 0));
+        assert_eq!(stream(&[b"a", b"", b"bc"], 0), stream(&[b"abc"], 0));
+        assert_eq!(stream(&[], 42), murmur_hash64a(b"", 42));
+
+        let data: Vec<u8> = (0..200u32).map(|i| (i * 7) as u8).collect();
+
+        for len in 0..data.len() {
+            // Up to 32 bytes are hashed in one go, longer input streams.
+            let expected = match len {
+                0..=32 => murmur_hash64a(&data[..len], 42),
+                _ => stream(&[&data[..len]], 42),
+            };
+
+            for split in 0..len + 1 {
+                let (a, b) = data[..len].split_at(split);
+                assert_eq!(stream(&[a, b], 42), expected, "len: {}, split: {}", len, split);
+            }
+
+            let chunks: Vec<&[u8]> = data[..len].chunks(3).collect();
+            assert_eq!(stream(&chunks, 42), expected, "len: {}, chunks of 3", len);
+        }
+    }
+
+    #[test]
+    fn long_input_streams() {
+        let zeros = [0u8; 100];
+
+        // The length is still mixed in, so zero padding changes the hash.
+        assert_ne!(stream(&[&zeros[..40]], 0), stream(&[&zeros[..41]], 0));
+        assert_ne!(stream(&[&zeros[..40]], 0), stream(&[&zeros[..48]], 0));
+        assert_ne!(stream(&[&zeros[..40]], 0), stream(&[&zeros[..40]], 1));
+
+        let key = "The quick brown fox jumps over the lazy dog".to_string();
+        let mut bytes = key.clone().into_bytes();
+        bytes.push(0xff);
+        assert_eq!(BuildMurmur2_64a::new().hash_one(&key), stream(&[&bytes], 0));
+
+        let mut hasher = Murmur2_64a::with_seed(21);
+        for i in 0..10u64 {
+            hasher.write_u64(i);
+        }
+        let expected: Vec<u8> = (0..10u64).flat_map(|i| i.to_le_bytes()).collect();
+        assert_eq!(hasher.finish(), stream(&[&expected], 21));
+    }
+
+    #[test]
+    fn integer_writes_match_one_shot() {
+        let mut hasher = Murmur2_64a::new();
+        hasher.write_u8(1);
+        hasher.write_u16(0x0302);
+        hasher.write_u32(0x07060504);
+        hasher.write_u64(0x0f0e0d0c0b0a0908);
+        hasher.write_usize(0x1716151413121110);
+
+        let expected: Vec<u8> = (1..0x18).collect();
+        assert_eq!(hasher.finish(), murmur_hash64a(&expected, 0));
+
+        let builder = BuildMurmur2_64a::with_seed(21);
+        assert_eq!(builder.hash_one(42u64), murmur_hash64a(&42u64.to_le_bytes(), 21));
+    }
+
+    #[test]
+    fn finish_does_not_consume() {
+        let mut hasher = Murmur2_64a::new();
+        "Eat my shorts".hash(&mut hasher);
+
+        let first = hasher.finish();
+        assert_eq!(hasher.finish(), first);
+
+        let mut bytes = b"Eat my shorts".to_vec();
+        bytes.push(0xff);
+        assert_eq!(first, murmur_hash64a(&bytes, 0));
+    }
+}
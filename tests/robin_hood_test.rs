@@ -0,0 +1,377 @@
+extern crate rand;
+
+extern crate fastmap;
+
+mod common;
+
+use common::IdentityHasher;
+use fastmap::{CheckPolicy, FastMap, FastMapConfig, RobinHoodMap, TryReserveError};
+use fastmap::robin_hood::Entry;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_get_remove() {
+        let data = get_random_range(10_000);
+        let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+
+        for s in data.iter() {
+            assert_eq!(map.insert(*s, *s), None);
+        }
+
+        assert_eq!(map.len(), data.len());
+        assert!(map.assert_count());
+
+        for s in data.iter() {
+            assert_eq!(map.get(s), Some(s));
+            assert_eq!(map.insert(*s, s + 1), Some(*s));
+        }
+
+        for s in data.iter().step_by(2) {
+            assert_eq!(map.remove(s), Some(s + 1));
+            assert_eq!(map.remove(s), None);
+        }
+
+        for (i, s) in data.iter().enumerate() {
+            assert_eq!(map.contains_key(s), i % 2 == 1);
+        }
+
+        assert_eq!(map.len(), data.len() / 2);
+        assert_eq!(map.iter().count(), map.len());
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn matches_std_map() {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+        let mut model = HashMap::new();
+
+        for i in 0..100_000u64 {
+            let key = rng.gen_range(0, 2000);
+
+            match rng.gen_range(0, 3) {
+                0 => assert_eq!(map.remove(&key), model.remove(&key)),
+                _ => assert_eq!(map.insert(key, i), model.insert(key, i)),
+            }
+        }
+
+        assert_eq!(map.len(), model.len());
+        for (k, v) in model.iter() {
+            assert_eq!(map.get(k), Some(v));
+        }
+    }
+
+    #[test]
+    fn remove_shifts_back() {
+        let mut map: RobinHoodMap<u64, u64, IdentityHasher> = RobinHoodMap::with_capacity_and_hasher(16, IdentityHasher(0));
+
+        // A run of keys sharing home bucket 3, with one from bucket 4 pushed behind them.
+        for k in [3, 19, 35, 4, 51].iter() {
+            map.insert(*k, *k);
+        }
+        assert_eq!(map.max_probe_length(), 4);
+
+        assert_eq!(map.remove(&3), Some(3));
+        assert_eq!(map.max_probe_length(), 3);
+
+        for k in [19, 35, 4, 51].iter() {
+            assert_eq!(map.get(k), Some(k));
+        }
+        assert_eq!(map.get(&67), None);
+    }
+
+    #[test]
+    fn wraps_at_tail() {
+        let mut map: RobinHoodMap<u64, u64, IdentityHasher> = RobinHoodMap::with_capacity_and_hasher(16, IdentityHasher(0));
+
+        for k in [15, 31, 47, 14].iter() {
+            map.insert(*k, *k);
+        }
+        assert_eq!(map.capacity(), 16);
+
+        for k in [15, 31, 47, 14].iter() {
+            assert_eq!(map.get(k), Some(k));
+        }
+
+        assert_eq!(map.remove(&15), Some(15));
+        assert_eq!(map.remove(&14), Some(14));
+        assert_eq!(map.get(&31), Some(&31));
+        assert_eq!(map.get(&47), Some(&47));
+    }
+
+    #[test]
+    fn probes_shorter_than_first_fit() {
+        let data = get_random_range(100_000);
+        let mut robin: RobinHoodMap<u64, u64> = RobinHoodMap::with_capacity(1 << 17);
+        let mut fast: FastMap<u64, u64> = FastMap::with_capacity(1 << 17);
+
+        for s in data[..(1 << 17) * 69 / 100].iter() {
+            robin.insert(*s, *s);
+            fast.insert(*s, *s);
+        }
+
+        assert_eq!(robin.capacity(), fast.capacity());
+        assert!(robin.max_probe_length() < fast.max_probe_length(),
+            "robin hood: {}, first fit: {}", robin.max_probe_length(), fast.max_probe_length());
+    }
+
+    #[test]
+    fn iterators() {
+        let data = get_random_range(1000);
+        let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+
+        for (_, v) in map.iter_mut() {
+            *v += 1;
+        }
+
+        let mut keys: Vec<u64> = map.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, data);
+
+        let sum: u64 = map.values().map(|v| v - 1).fold(0, |a, v| a.wrapping_add(v));
+        assert_eq!(sum, data.iter().fold(0, |a: u64, v| a.wrapping_add(*v)));
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    fn entry_api() {
+        let data = get_random_range(1000);
+        let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+
+        for s in data.iter() {
+            *map.entry(*s).or_insert(0) += 1;
+        }
+        for s in data.iter().step_by(2) {
+            map.entry(*s).and_modify(|v| *v += 1).or_insert(0);
+        }
+
+        for (i, s) in data.iter().enumerate() {
+            assert_eq!(map.get(s), Some(&if i % 2 == 0 { 2 } else { 1 }));
+        }
+
+        for s in data.iter().step_by(3) {
+            match map.entry(*s) {
+                Entry::Occupied(entry) => assert_eq!(entry.remove_entry().0, *s),
+                Entry::Vacant(_) => panic!("{} should be in the map", s),
+            }
+        }
+
+        assert_eq!(map.len(), data.len() - data.len().div_ceil(3));
+        assert!(map.assert_count());
+
+        assert!(map.try_insert(data[0], 7).is_ok());
+        let err = map.try_insert(data[0], 8).unwrap_err();
+        assert_eq!((*err.entry.get(), err.value), (7, 8));
+    }
+
+    #[test]
+    fn follows_config() {
+        let config = FastMapConfig::new().max_load_factor(0.5).growth_factor(4).min_load_factor(0.1);
+        let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::with_config(config);
+
+        for i in 0..1000 {
+            map.insert(i, i);
+            assert!(map.load_rate() <= 50.0);
+        }
+        assert_eq!(map.capacity(), 4096);
+
+        for i in 0..990 {
+            map.remove(&i);
+        }
+        assert!(map.capacity() < 4096);
+        assert!(map.assert_count());
+
+        map.shrink_to_fit();
+        assert_eq!(map.capacity(), 32);
+
+        for i in 990..1000 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+
+        let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::with_config(FastMapConfig::new().check_policy(CheckPolicy::Amortized(64)));
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.len(), 10_000);
+        assert!(map.load_rate() <= 70.0);
+    }
+
+    #[test]
+    fn try_reserve_reports_overflow() {
+        let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+        map.insert(21, 42);
+
+        assert_eq!(map.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(map.try_reserve(usize::MAX / 2), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(map.get(&21), Some(&42));
+
+        assert_eq!(map.try_reserve(1000), Ok(()));
+        let capacity = map.capacity();
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.capacity(), capacity);
+    }
+
+    #[test]
+    fn extract_if_walks_wrapped_runs() {
+        let mut map: RobinHoodMap<u64, u64, IdentityHasher> = RobinHoodMap::with_capacity_and_hasher(16, IdentityHasher(0));
+
+        // A run starting at bucket 14 wraps around, pushing 0 and 1 out of their home buckets.
+        let keys = [14, 30, 46, 62, 0, 1];
+        for k in keys.iter() {
+            map.insert(*k, *k);
+        }
+        assert_eq!(map.capacity(), 16);
+
+        let mut seen = Vec::new();
+        let mut taken: Vec<(u64, u64)> = map.extract_if(|k, _| { seen.push(*k); k % 32 == 30 }).collect();
+        taken.sort();
+        seen.sort();
+
+        // Every entry is looked at once, even the ones shifted back across the end.
+        assert_eq!(taken, [(30, 30), (62, 62)]);
+        assert_eq!(seen, [0, 1, 14, 30, 46, 62]);
+
+        for k in [14, 46, 0, 1].iter() {
+            assert_eq!(map.get(k), Some(k));
+        }
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.max_probe_length(), 2);
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn retain_and_drain() {
+        let data = get_random_range(1000);
+        let mut map: RobinHoodMap<u64, u64> = data.iter().map(|&s| (s, s)).collect();
+
+        map.retain(|k, v| { *v = v.wrapping_add(1); k % 2 == 0 });
+        for s in data.iter() {
+            assert_eq!(map.get(s).cloned(), if s % 2 == 0 { Some(s.wrapping_add(1)) } else { None });
+        }
+        assert!(map.assert_count());
+
+        let len = map.len();
+        let mut drained: Vec<(u64, u64)> = map.drain().collect();
+        drained.sort();
+        assert_eq!(drained.len(), len);
+        assert!(drained.iter().all(|&(k, v)| k % 2 == 0 && v == k.wrapping_add(1)));
+        assert!(map.is_empty());
+        assert!(map.assert_count());
+
+        let counter = Rc::new(());
+        let mut map: RobinHoodMap<u64, Rc<()>> = RobinHoodMap::new();
+        for i in 0..100 {
+            map.insert(i, counter.clone());
+        }
+
+        let mut drain = map.drain();
+        assert_eq!(drain.len(), 100);
+        drain.next();
+        drop(drain);
+        assert_eq!(Rc::strong_count(&counter), 1);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn owned_and_double_ended_iterators() {
+        let data = get_random_range(1000);
+        let mut map: RobinHoodMap<u64, u64> = RobinHoodMap::new();
+
+        for s in data.iter() {
+            assert_eq!(map.insert_fallible(*s, *s), Ok(None));
+        }
+        assert_eq!(map.insert_fallible(data[0], 1), Ok(Some(data[0])));
+        map.insert(data[0], data[0]);
+
+        let forward: Vec<_> = map.iter().collect();
+        let mut backward: Vec<_> = map.iter().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        let mut iter = map.iter();
+        assert_eq!(iter.len(), data.len());
+        iter.next();
+        iter.next_back();
+        assert_eq!(iter.clone().count(), data.len() - 2);
+        assert_eq!(iter.len(), data.len() - 2);
+        assert_eq!(map.keys().len(), data.len());
+        assert_eq!(map.values().rev().len(), data.len());
+        assert_eq!(map.values_mut().len(), data.len());
+
+        let mut keys: Vec<u64> = map.clone().into_keys().collect();
+        let mut values: Vec<u64> = map.into_values().rev().collect();
+        keys.sort();
+        values.sort();
+        assert_eq!(keys, data);
+        assert_eq!(values, data);
+    }
+
+    #[test]
+    #[should_panic(expected = "RobinHoodMap doesn't resize incrementally")]
+    fn rejects_incremental_resize() {
+        let _map: RobinHoodMap<u64, u64> = RobinHoodMap::with_config(FastMapConfig::new().incremental_resize(64));
+    }
+
+    #[test]
+    fn std_traits() {
+        let data = get_random_range(1000);
+
+        let map: RobinHoodMap<u64, u64> = data.iter().map(|&s| (s, s / 2)).collect();
+        assert_eq!(map.len(), data.len());
+        assert_eq!(map[&data[21]], data[21] / 2);
+
+        let copy = map.clone();
+        assert_eq!(copy, map);
+
+        let mut other: RobinHoodMap<u64, u64> = RobinHoodMap::default();
+        other.extend(map.iter());
+        assert_eq!(other, map);
+        other.insert(data[0], 0);
+        assert_ne!(other, map);
+
+        let mut owned: Vec<(u64, u64)> = map.into_iter().collect();
+        owned.sort();
+        assert_eq!(owned, data.iter().map(|&s| (s, s / 2)).collect::<Vec<_>>());
+
+        let small = RobinHoodMap::<u64, u64>::from([(21, 42)]);
+        assert_eq!(format!("{:?}", small), "{21: 42}");
+    }
+
+
+    fn get_random_range(count: usize) -> Vec<u64> {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let mut vec = Vec::new();
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        for _ in 0..count {
+            vec.push(rng.gen::<u64>());
+        }
+
+        vec.sort();
+        vec.dedup();
+
+        vec
+    }
+}
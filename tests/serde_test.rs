@@ -0,0 +1,108 @@
+#![cfg(feature = "serde")]
+
+extern crate rand;
+extern crate serde_json;
+
+extern crate fastmap;
+
+use fastmap::{FastMap, FastSet, SplitMap};
+use fastmap::serde_support::{deserialize_unique, deserialize_unique_set};
+
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_round_trip() {
+        let data = get_random_range(1000);
+        let mut map: FastMap<u64, String> = FastMap::new();
+
+        for s in data.iter() {
+            map.insert(*s, s.to_string());
+        }
+
+        let json = serde_json::to_string(&map).unwrap();
+        let back: FastMap<u64, String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, map);
+
+        // Same format as the std map.
+        let std_map: HashMap<u64, String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(std_map.len(), map.len());
+        let back: FastMap<u64, String> = serde_json::from_str(&serde_json::to_string(&std_map).unwrap()).unwrap();
+        assert_eq!(back, map);
+
+        let split: SplitMap<u64, String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_value(&split).unwrap(), serde_json::to_value(&map).unwrap());
+    }
+
+    #[test]
+    fn map_serializes_as_object() {
+        let map: FastMap<String, u64> = FastMap::from([("Bart".to_string(), 10)]);
+
+        assert_eq!(serde_json::to_string(&map).unwrap(), r#"{"Bart":10}"#);
+
+        let empty: FastMap<String, u64> = serde_json::from_str("{}").unwrap();
+        assert!(empty.is_empty());
+        assert!(serde_json::from_str::<FastMap<String, u64>>("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let json = r#"{"Bart": 10, "Lisa": 8, "Bart": 11}"#;
+
+        let map: FastMap<String, u64> = serde_json::from_str(json).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["Bart"], 11);
+
+        let mut de = serde_json::Deserializer::from_str(json);
+        let res: Result<FastMap<String, u64>, _> = deserialize_unique(&mut de);
+        assert!(res.unwrap_err().to_string().contains("duplicate key in map"));
+
+        let mut de = serde_json::Deserializer::from_str(r#"{"Bart": 10, "Lisa": 8}"#);
+        let map: FastMap<String, u64> = deserialize_unique(&mut de).unwrap();
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn set_round_trip() {
+        let data = get_random_range(1000);
+        let set: FastSet<u64> = data.iter().cloned().collect();
+
+        let json = serde_json::to_string(&set).unwrap();
+        let back: FastSet<u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.len(), set.len());
+        assert!(back.is_subset(&set));
+
+        let mut values: Vec<u64> = serde_json::from_str(&json).unwrap();
+        values.sort();
+        assert_eq!(values, data);
+
+        let set: FastSet<u64> = serde_json::from_str("[1, 2, 1]").unwrap();
+        assert_eq!(set.len(), 2);
+
+        let mut de = serde_json::Deserializer::from_str("[1, 2, 1]");
+        let res: Result<FastSet<u64>, _> = deserialize_unique_set(&mut de);
+        assert!(res.is_err());
+    }
+
+
+    fn get_random_range(count: usize) -> Vec<u64> {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let mut vec = Vec::new();
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        for _ in 0..count {
+            vec.push(rng.gen::<u64>());
+        }
+
+        vec.sort();
+        vec.dedup();
+
+        vec
+    }
+}
@@ -0,0 +1,196 @@
+extern crate rand;
+
+extern crate fastmap;
+
+use fastmap::FastSet;
+
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_contains_remove() {
+        let data = get_random_range(10_000);
+        let mut set: FastSet<u64> = FastSet::new();
+
+        for s in data.iter() {
+            assert!(set.insert(*s));
+            assert!(!set.insert(*s));
+        }
+        assert_eq!(set.len(), data.len());
+
+        for s in data.iter().step_by(2) {
+            assert!(set.remove(s));
+            assert!(!set.remove(s));
+        }
+
+        for (i, s) in data.iter().enumerate() {
+            assert_eq!(set.contains(s), i % 2 == 1);
+        }
+        assert_eq!(set.iter().count(), data.len() / 2);
+    }
+
+    #[test]
+    fn get_take_replace() {
+        let mut set: FastSet<String> = FastSet::new();
+        set.insert("Bart".to_string());
+
+        assert_eq!(set.get("Bart").map(String::as_str), Some("Bart"));
+        assert_eq!(set.replace("Bart".to_string()), Some("Bart".to_string()));
+        assert_eq!(set.replace("Lisa".to_string()), None);
+        assert_eq!(set.len(), 2);
+
+        assert_eq!(set.take("Lisa"), Some("Lisa".to_string()));
+        assert_eq!(set.take("Lisa"), None);
+        assert_eq!(set.get("Lisa"), None);
+        assert_eq!(set.len(), 1);
+    }
+
+    /// Equal on `id` only, so replacing shows which of two equal values is stored.
+    #[derive(Debug)]
+    struct Tagged {
+        id: u64,
+        tag: u64,
+    }
+
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Tagged) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl Eq for Tagged {}
+
+    impl Hash for Tagged {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.id.hash(state);
+        }
+    }
+
+    /// Counts the keys hashed, which is one per probe of the table.
+    #[derive(Clone, Default)]
+    struct CountingState(Rc<Cell<usize>>);
+
+    impl BuildHasher for CountingState {
+        type Hasher = DefaultHasher;
+
+        fn build_hasher(&self) -> DefaultHasher {
+            self.0.set(self.0.get() + 1);
+            DefaultHasher::new()
+        }
+    }
+
+    #[test]
+    fn replace_swaps_in_place() {
+        let data = get_random_range(1000);
+        let hashed = CountingState::default();
+        let mut set = FastSet::with_hasher(hashed.clone());
+        set.extend(data.iter().map(|&id| Tagged { id, tag: 0 }));
+
+        for round in 1..5 {
+            for &id in data.iter() {
+                hashed.0.set(0);
+                let old = set.replace(Tagged { id, tag: round }).unwrap();
+                assert_eq!(old.tag, round - 1);
+                assert_eq!(hashed.0.get(), 1);
+            }
+        }
+
+        assert_eq!(set.len(), data.len());
+        assert!(set.iter().all(|t| t.tag == 4));
+    }
+
+    #[test]
+    fn std_traits() {
+        let data = get_random_range(1000);
+        let set: FastSet<u64> = data.iter().cloned().collect();
+
+        let copy = set.clone();
+        assert_eq!(copy, set);
+
+        let mut other: FastSet<u64> = FastSet::default();
+        for s in &set {
+            other.insert(*s);
+        }
+        assert_eq!(other, set);
+        other.remove(&data[0]);
+        assert_ne!(other, set);
+
+        let mut owned: Vec<u64> = set.into_iter().collect();
+        owned.sort();
+        assert_eq!(owned, data);
+
+        let small: FastSet<u64> = Some(21).into_iter().collect();
+        assert_eq!(format!("{:?}", small), "{21}");
+    }
+
+    #[test]
+    fn set_algebra_matches_std() {
+        let data = get_random_range(2000);
+        let (a, b): (Vec<u64>, Vec<u64>) = (data[..1200].to_vec(), data[800..].to_vec());
+
+        let fa: FastSet<u64> = a.iter().cloned().collect();
+        let fb: FastSet<u64> = b.iter().cloned().collect();
+        let sa: HashSet<u64> = a.iter().cloned().collect();
+        let sb: HashSet<u64> = b.iter().cloned().collect();
+
+        assert_eq!(sorted(fa.union(&fb)), sorted(sa.union(&sb)));
+        assert_eq!(sorted(fa.intersection(&fb)), sorted(sa.intersection(&sb)));
+        assert_eq!(sorted(fb.intersection(&fa)), sorted(sa.intersection(&sb)));
+        assert_eq!(sorted(fa.difference(&fb)), sorted(sa.difference(&sb)));
+        assert_eq!(sorted(fa.symmetric_difference(&fb)), sorted(sa.symmetric_difference(&sb)));
+
+        assert_eq!(sorted((&fa | &fb).iter()), sorted(sa.union(&sb)));
+        assert_eq!(sorted((&fa & &fb).iter()), sorted(sa.intersection(&sb)));
+        assert_eq!(sorted((&fa - &fb).iter()), sorted(sa.difference(&sb)));
+        assert_eq!(sorted((&fa ^ &fb).iter()), sorted(sa.symmetric_difference(&sb)));
+    }
+
+    #[test]
+    fn subset_and_disjoint() {
+        let small: FastSet<u64> = (0..10).collect();
+        let large: FastSet<u64> = (0..100).collect();
+        let other: FastSet<u64> = (100..110).collect();
+        let empty: FastSet<u64> = FastSet::new();
+
+        assert!(small.is_subset(&large));
+        assert!(!large.is_subset(&small));
+        assert!(large.is_superset(&small));
+        assert!(empty.is_subset(&small));
+
+        assert!(small.is_disjoint(&other));
+        assert!(!small.is_disjoint(&large));
+        assert!(empty.is_disjoint(&empty));
+    }
+
+
+    fn sorted<'a, I: Iterator<Item = &'a u64>>(iter: I) -> Vec<u64> {
+        let mut vec: Vec<u64> = iter.cloned().collect();
+        vec.sort();
+        vec
+    }
+
+    fn get_random_range(count: usize) -> Vec<u64> {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let mut vec = Vec::new();
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        for _ in 0..count {
+            vec.push(rng.gen::<u64>());
+        }
+
+        vec.sort();
+        vec.dedup();
+
+        vec
+    }
+}
@@ -0,0 +1,329 @@
+extern crate rand;
+
+extern crate fastmap;
+
+use fastmap::{BuildMurmur2_64a, FastMap, FastMapConfig, SnapshotError, SplitMap};
+use fastmap::hashers::{BuildXxHash64, XxHash64};
+use fastmap::snapshot::SnapshotView;
+
+use std::hash::Hasher;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_random_range(count: usize) -> Vec<u64> {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let mut vec = Vec::new();
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        for _ in 0..count {
+            vec.push(rng.gen::<u64>() % 100_000);
+        }
+
+        vec.sort();
+        vec.dedup();
+
+        vec
+    }
+
+    fn snapshot(map: &FastMap<u64, u64>) -> Vec<u8> {
+        let mut buf = Vec::new();
+        map.save_to(&mut buf).unwrap();
+        buf
+    }
+
+    /// Rewrites the checksum after the snapshot was tampered with.
+    fn reseal(buf: &mut [u8]) {
+        let end = buf.len() - 8;
+        let mut hasher = XxHash64::new();
+        hasher.write(&buf[..end]);
+        buf[end..].copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+
+    fn assert_corrupt(buf: &[u8], what: &str) {
+        match FastMap::<u64, u64>::load_from(buf) {
+            Err(SnapshotError::Corrupt(found)) if found == what => {}
+            res => panic!("expected {:?}, got {:?}", what, res.err()),
+        }
+
+        match SnapshotView::<u64, u64>::new(buf) {
+            Err(SnapshotError::Corrupt(found)) if found == what => {}
+            res => panic!("expected {:?} from the view, got {:?}", what, res.err()),
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_layout() {
+        let mut map: FastMap<u64, u64> = FastMap::with_hasher(BuildMurmur2_64a::with_seed(42));
+        let values = get_random_range(10_000);
+
+        for &v in &values {
+            map.insert(v, v * 2);
+        }
+
+        for &v in values.iter().step_by(3) {
+            map.remove(&v);
+        }
+
+        let buf = snapshot(&map);
+        let loaded: FastMap<u64, u64> = FastMap::load_from(&buf[..]).unwrap();
+
+        assert_eq!(loaded, map);
+        assert_eq!(loaded.hasher().seed(), 42);
+        assert_eq!(loaded.capacity(), map.capacity());
+        assert_eq!(loaded.tombstones(), map.tombstones());
+        assert!(loaded.iter().eq(map.iter()));
+        assert!(loaded.assert_count());
+
+        // Saving the loaded map again gives the same bytes.
+        assert_eq!(snapshot(&loaded), buf);
+    }
+
+    #[test]
+    fn loaded_map_stays_usable() {
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+
+        let mut loaded: FastMap<u64, u64> = FastMap::load_from(&snapshot(&map)[..]).unwrap();
+
+        for i in 1000..5000 {
+            loaded.insert(i, i);
+        }
+
+        for i in 0..2000 {
+            assert_eq!(loaded.remove(&i), Some(i));
+        }
+
+        assert_eq!(loaded.len(), 3000);
+        assert_eq!(loaded.get(&4999), Some(&4999));
+        assert!(loaded.assert_count());
+    }
+
+    #[test]
+    fn round_trip_while_resizing() {
+        let mut map: FastMap<u64, u64> = FastMap::with_config(FastMapConfig::new().incremental_resize(2));
+
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        assert!(map.is_resizing());
+
+        let mut loaded: FastMap<u64, u64> = FastMap::load_from(&snapshot(&map)[..]).unwrap();
+
+        assert!(loaded.is_resizing());
+        assert_eq!(loaded, map);
+
+        for i in 100..200 {
+            loaded.insert(i, i);
+        }
+        assert_eq!(loaded.len(), 200);
+        assert!(loaded.assert_count());
+    }
+
+    #[test]
+    fn round_trip_keeps_config() {
+        let config = FastMapConfig::new().capacity(64).max_load_factor(0.9).growth_factor(4);
+        let mut map: FastMap<u64, u64> = FastMap::with_config(config);
+
+        for i in 0..55 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.capacity(), 64);
+
+        let buf = snapshot(&map);
+        let view: SnapshotView<u64, u64> = SnapshotView::new(&buf).unwrap();
+        assert_eq!(view.get(&54), Some(54));
+
+        let mut loaded: FastMap<u64, u64> = FastMap::load_from(&buf[..]).unwrap();
+        assert_eq!(loaded, map);
+        assert_eq!(loaded.capacity(), 64);
+
+        // Keeps the saved 90% load limit and growth factor.
+        for i in 55..60 {
+            loaded.insert(i, i);
+            map.insert(i, i);
+        }
+        assert_eq!(loaded.capacity(), 256);
+        assert_eq!(snapshot(&loaded), snapshot(&map));
+    }
+
+    #[test]
+    fn round_trip_split_and_arrays() {
+        let mut map: SplitMap<u32, [u64; 4]> = FastMap::new_split();
+
+        for i in 0..500 {
+            map.insert(i, [i as u64; 4]);
+        }
+
+        let mut buf = Vec::new();
+        map.save_to(&mut buf).unwrap();
+
+        let loaded: SplitMap<u32, [u64; 4]> = FastMap::load_from(&buf[..]).unwrap();
+        assert_eq!(loaded, map);
+
+        // The bucket layout doesn't depend on the storage.
+        let interleaved: FastMap<u32, [u64; 4]> = FastMap::load_from(&buf[..]).unwrap();
+        assert_eq!(interleaved.get(&21), Some(&[21; 4]));
+    }
+
+    #[test]
+    fn rejects_mismatches() {
+        let mut map: FastMap<u64, u64> = FastMap::new();
+        map.insert(21, 42);
+        let buf = snapshot(&map);
+
+        match FastMap::<u64, u64, BuildXxHash64>::load_from(&buf[..]) {
+            Err(SnapshotError::HasherMismatch { expected: 3, found: 1 }) => {}
+            res => panic!("expected a hasher mismatch, got {:?}", res.err()),
+        }
+
+        match FastMap::<u64, u32>::load_from(&buf[..]) {
+            Err(SnapshotError::LayoutMismatch) => {}
+            res => panic!("expected a layout mismatch, got {:?}", res.err()),
+        }
+
+        // Same sizes, other types.
+        match FastMap::<i64, u64>::load_from(&buf[..]) {
+            Err(SnapshotError::LayoutMismatch) => {}
+            res => panic!("expected a layout mismatch, got {:?}", res.err()),
+        }
+        match SnapshotView::<[u8; 8], u64>::new(&buf) {
+            Err(SnapshotError::LayoutMismatch) => {}
+            res => panic!("expected a layout mismatch, got {:?}", res.err()),
+        }
+
+        let mut newer = buf.clone();
+        newer[8] = 3;
+        match FastMap::<u64, u64>::load_from(&newer[..]) {
+            Err(SnapshotError::UnsupportedVersion(3)) => {}
+            res => panic!("expected an unsupported version, got {:?}", res.err()),
+        }
+
+        match FastMap::<u64, u64>::load_from(&b"not a snapshot at all"[..]) {
+            Err(SnapshotError::BadMagic) => {}
+            res => panic!("expected bad magic, got {:?}", res.err()),
+        }
+    }
+
+    #[test]
+    fn rejects_damage() {
+        let mut map: FastMap<u64, u64> = FastMap::new();
+
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        let buf = snapshot(&map);
+
+        let mut flipped = buf.clone();
+        let last = flipped.len() - 9;
+        flipped[last] ^= 1;
+        match FastMap::<u64, u64>::load_from(&flipped[..]) {
+            Err(SnapshotError::ChecksumMismatch) => {}
+            res => panic!("expected a checksum mismatch, got {:?}", res.err()),
+        }
+
+        match FastMap::<u64, u64>::load_from(&buf[..buf.len() - 1]) {
+            Err(SnapshotError::Io(_)) => {}
+            res => panic!("expected an i/o error, got {:?}", res.err()),
+        }
+
+        // A table far larger than the input is never allocated.
+        let mut huge = buf.clone();
+        huge[48..52].copy_from_slice(&40u32.to_le_bytes());
+        match FastMap::<u64, u64>::load_from(&huge[..]) {
+            Err(SnapshotError::Io(_)) => {}
+            res => panic!("expected an i/o error, got {:?}", res.err()),
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_tables() {
+        // A table of 2 buckets, both full.
+        let mut buf = snapshot(&FastMap::new())[..112].to_vec();
+        buf[48..52].copy_from_slice(&1u32.to_le_bytes());
+        buf[52..60].copy_from_slice(&2u64.to_le_bytes());
+        for key in 0..2u64 {
+            buf.push(2);
+            buf.extend_from_slice(&key.to_le_bytes());
+            buf.extend_from_slice(&key.to_le_bytes());
+            buf.extend_from_slice(&key.to_le_bytes());
+        }
+        buf.extend_from_slice(&[0; 8]);
+        reseal(&mut buf);
+        assert_corrupt(&buf, "no empty bucket to end a probe");
+
+        let mut map: FastMap<u64, u64> = FastMap::new();
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        let buf = snapshot(&map);
+        let records = buf[112..buf.len() - 8].chunks(25).count();
+
+        // Tombstones in all but one empty bucket.
+        let mut overloaded = buf.clone();
+        let empty: Vec<usize> = (0..records).map(|ix| 112 + ix * 25).filter(|&at| buf[at] == 0).collect();
+        for &at in &empty[1..] {
+            overloaded[at] = 1;
+        }
+        reseal(&mut overloaded);
+        assert_corrupt(&overloaded, "table filled past its load limit");
+
+        let mut miscounted = buf.clone();
+        miscounted[52..60].copy_from_slice(&99u64.to_le_bytes());
+        reseal(&mut miscounted);
+        assert_corrupt(&miscounted, "count doesn't match the full buckets");
+    }
+
+    #[test]
+    fn view_reads_in_place() {
+        let mut map: FastMap<u64, u64> = FastMap::with_hasher(BuildMurmur2_64a::with_seed(42));
+        let values = get_random_range(10_000);
+
+        for &v in &values {
+            map.insert(v, v * 2);
+        }
+        for &v in values.iter().step_by(3) {
+            map.remove(&v);
+        }
+
+        let buf = snapshot(&map);
+        let view: SnapshotView<u64, u64> = SnapshotView::new(&buf).unwrap();
+
+        assert_eq!(view.len(), map.len());
+        assert_eq!(view.hasher().seed(), 42);
+        for &v in &values {
+            assert_eq!(view.get(&v), map.get(&v).cloned());
+        }
+        assert!(!view.contains_key(&100_000));
+        assert!(view.iter().eq(map.iter().map(|(k, v)| (*k, *v))));
+
+        match SnapshotView::<u64, u64>::new(&buf[..buf.len() - 1]) {
+            Err(SnapshotError::Io(_)) => {}
+            res => panic!("expected an i/o error, got {:?}", res.err()),
+        }
+    }
+
+    #[test]
+    fn view_while_resizing() {
+        let mut map: FastMap<u64, u64> = FastMap::with_config(FastMapConfig::new().incremental_resize(2));
+
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        assert!(map.is_resizing());
+
+        let buf = snapshot(&map);
+        let view: SnapshotView<u64, u64> = SnapshotView::new(&buf).unwrap();
+
+        assert_eq!(view.len(), 100);
+        assert!((0..100).all(|i| view.get(&i) == Some(i)));
+        assert_eq!(view.iter().count(), 100);
+    }
+}
@@ -0,0 +1,97 @@
+extern crate rand;
+
+extern crate fastmap;
+
+use fastmap::{BuildMurmur2_64a, FastMap, FastMapConfig, SplitMap};
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_std_map() {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut map: SplitMap<u64, u64> = FastMap::new_split();
+        let mut model = HashMap::new();
+
+        for i in 0..100_000u64 {
+            let key = rng.gen_range(0, 2000);
+
+            match rng.gen_range(0, 3) {
+                0 => assert_eq!(map.remove(&key), model.remove(&key)),
+                _ => assert_eq!(map.insert(key, i), model.insert(key, i)),
+            }
+        }
+
+        assert_eq!(map.len(), model.len());
+        assert!(map.assert_count());
+        for (k, v) in model.iter() {
+            assert_eq!(map.get(k), Some(v));
+        }
+    }
+
+    #[test]
+    fn incremental_resize() {
+        let config = FastMapConfig::new().incremental_resize(8);
+        let mut map: SplitMap<u64, Vec<u64>> = FastMap::with_config_and_hasher(config, BuildMurmur2_64a::new());
+
+        for i in 0..10_000u64 {
+            map.insert(i, vec![i]);
+        }
+
+        for (k, v) in map.iter_mut() {
+            v.push(*k);
+        }
+
+        for i in 0..10_000u64 {
+            assert_eq!(map.get(&i), Some(&vec![i, i]));
+        }
+        assert_eq!(map.iter().count(), 10_000);
+        assert!(map.assert_count());
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    fn drops_every_entry_once() {
+        let counter = Rc::new(());
+
+        {
+            let mut map: SplitMap<u64, Rc<()>> = FastMap::new_split();
+
+            for i in 0..1000 {
+                map.insert(i, counter.clone());
+            }
+            for i in 0..500 {
+                map.remove(&i);
+            }
+            assert_eq!(Rc::strong_count(&counter), 501);
+
+            let copy = map.clone();
+            assert_eq!(Rc::strong_count(&counter), 1001);
+
+            let mut owned = copy.into_iter();
+            owned.next();
+            drop(owned);
+            assert_eq!(Rc::strong_count(&counter), 501);
+
+            map.clear();
+            assert_eq!(Rc::strong_count(&counter), 1);
+
+            for i in 0..100 {
+                map.insert(i, counter.clone());
+            }
+        }
+
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+}
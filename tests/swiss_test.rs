@@ -0,0 +1,481 @@
+extern crate rand;
+
+extern crate fastmap;
+
+mod common;
+
+use common::IdentityHasher;
+use fastmap::{BuildMurmur2_64a, CheckPolicy, Entry, FastMap, FastMapConfig, SwissMap, TryReserveError};
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_get_remove() {
+        let data = get_random_range(10_000);
+        let mut map: SwissMap<u64, u64> = SwissMap::new_swiss();
+
+        for s in data.iter() {
+            assert_eq!(map.insert(*s, *s), None);
+        }
+
+        assert_eq!(map.len(), data.len());
+        assert!(map.assert_count());
+
+        for s in data.iter() {
+            assert_eq!(map.get(s), Some(s));
+            assert_eq!(map.insert(*s, s + 1), Some(*s));
+        }
+
+        for s in data.iter().step_by(2) {
+            assert_eq!(map.remove(s), Some(s + 1));
+            assert_eq!(map.remove(s), None);
+        }
+
+        for (i, s) in data.iter().enumerate() {
+            assert_eq!(map.contains_key(s), i % 2 == 1);
+        }
+
+        assert_eq!(map.len(), data.len() / 2);
+        assert_eq!(map.iter().count(), map.len());
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn matches_std_map() {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut map: SwissMap<u64, u64> = SwissMap::new_swiss();
+        let mut model = HashMap::new();
+
+        for i in 0..100_000u64 {
+            let key = rng.gen_range(0, 2000);
+
+            match rng.gen_range(0, 3) {
+                0 => assert_eq!(map.remove(&key), model.remove(&key)),
+                _ => assert_eq!(map.insert(key, i), model.insert(key, i)),
+            }
+        }
+
+        assert_eq!(map.len(), model.len());
+        assert!(map.assert_count());
+        for (k, v) in model.iter() {
+            assert_eq!(map.get(k), Some(v));
+        }
+    }
+
+    #[test]
+    fn churn_clears_tombstones() {
+        let data = get_random_range(100_000);
+        let mut map: SwissMap<u64, u64> = SwissMap::new_swiss();
+
+        for s in data[..1000].iter() {
+            map.insert(*s, *s);
+        }
+
+        for (i, s) in data[1000..].iter().enumerate() {
+            assert_eq!(map.remove(&data[i]), Some(data[i]));
+            map.insert(*s, *s);
+        }
+
+        assert_eq!(map.len(), 1000);
+        assert!(map.capacity() <= 2048, "map grew to {} slots", map.capacity());
+        assert!((map.len() + map.tombstones()) * 10 <= map.capacity() * 7);
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn same_tag_and_tail() {
+        let mut map: SwissMap<u64, u64, IdentityHasher> = SwissMap::with_capacity_and_hasher(32, IdentityHasher(0));
+
+        // Small keys all share the 0 tag, and these all start probing in the last bucket.
+        let mut keys: Vec<u64> = (0..13).map(|i| 31 + i * 32).collect();
+        keys.push(30);
+        for k in keys.iter() {
+            map.insert(*k, *k);
+        }
+
+        assert_eq!(map.capacity(), 32);
+        assert!(map.assert_count());
+
+        for k in keys.iter() {
+            assert_eq!(map.get(k), Some(k));
+        }
+        assert_eq!(map.get(&447), None);
+
+        for k in keys.iter().step_by(2) {
+            assert_eq!(map.remove(k), Some(*k));
+        }
+        for (i, k) in keys.iter().enumerate() {
+            assert_eq!(map.contains_key(k), i % 2 == 1);
+        }
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn long_probes_cross_groups() {
+        let mut map: SwissMap<u64, u64, IdentityHasher> = SwissMap::with_capacity_and_hasher(64, IdentityHasher(0));
+
+        // All start probing at bucket 60, so the run wraps around and spans several groups.
+        let keys: Vec<u64> = (0..40).map(|i| 60 + i * 64).collect();
+        for k in keys.iter() {
+            map.insert(*k, *k);
+        }
+        assert_eq!(map.capacity(), 64);
+        assert_eq!(map.max_probe_length(), 40);
+
+        for k in keys.iter().step_by(2) {
+            assert_eq!(map.remove(k), Some(*k));
+        }
+        for (i, k) in keys.iter().enumerate() {
+            assert_eq!(map.get(k), if i % 2 == 1 { Some(k) } else { None });
+        }
+
+        // Reuses tombstones instead of going past the end of the run.
+        for k in keys.iter().step_by(2) {
+            assert_eq!(map.insert(*k, k + 1), None);
+        }
+        assert_eq!(map.tombstones(), 0);
+        assert_eq!(map.max_probe_length(), 40);
+        for (i, k) in keys.iter().enumerate() {
+            assert_eq!(map.get(k), Some(&if i % 2 == 0 { k + 1 } else { *k }));
+        }
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn tables_smaller_than_a_group() {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut map: SwissMap<u64, u64, IdentityHasher> = SwissMap::with_capacity_and_hasher(2, IdentityHasher(0));
+        let mut model = HashMap::new();
+
+        for i in 0..10_000u64 {
+            let key = rng.gen_range(0, 6);
+
+            match rng.gen_range(0, 2) {
+                0 => assert_eq!(map.remove(&key), model.remove(&key)),
+                _ => assert_eq!(map.insert(key, i), model.insert(key, i)),
+            }
+
+            for k in 0..6 {
+                assert_eq!(map.get(&k), model.get(&k));
+            }
+        }
+
+        assert!(map.capacity() <= 16);
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn defaults_match_fast_map() {
+        let mut swiss: SwissMap<u64, u64> = SwissMap::new_swiss();
+        let mut fast: FastMap<u64, u64> = FastMap::new();
+        assert_eq!(swiss.config(), fast.config());
+
+        for i in 0..1000 {
+            swiss.insert(i, i);
+            fast.insert(i, i);
+            assert_eq!(swiss.capacity(), fast.capacity());
+        }
+    }
+
+    #[test]
+    fn drops_every_entry_once() {
+        let counter = Rc::new(());
+
+        {
+            let mut map: SwissMap<u64, Rc<()>> = SwissMap::new_swiss();
+
+            for i in 0..1000 {
+                map.insert(i, counter.clone());
+            }
+            for i in 0..500 {
+                map.remove(&i);
+            }
+            for i in 500..600 {
+                map.insert(i, counter.clone());
+            }
+
+            assert_eq!(Rc::strong_count(&counter), 501);
+
+            map.clear();
+            assert_eq!(Rc::strong_count(&counter), 1);
+
+            for i in 0..100 {
+                map.insert(i, counter.clone());
+            }
+        }
+
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn iterators() {
+        let data = get_random_range(1000);
+        let mut map: SwissMap<u64, u64> = SwissMap::new_swiss();
+
+        for s in data.iter() {
+            map.insert(*s, *s);
+        }
+
+        for (_, v) in map.iter_mut() {
+            *v = v.wrapping_add(1);
+        }
+
+        let mut keys: Vec<u64> = map.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, data);
+
+        for (k, v) in map.iter() {
+            assert_eq!(k.wrapping_add(1), *v);
+        }
+        assert_eq!(map.values().count(), data.len());
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    fn string_keys() {
+        let mut map: SwissMap<String, usize> = SwissMap::new_swiss();
+
+        for i in 0..1000 {
+            map.insert(format!("item: {}", i), i);
+        }
+
+        for i in 0..1000 {
+            assert_eq!(map.get(format!("item: {}", i).as_str()), Some(&i));
+        }
+        assert_eq!(map.get("item: 1000"), None);
+    }
+
+
+    #[test]
+    fn entry_api() {
+        let data = get_random_range(1000);
+        let mut map: SwissMap<u64, u64> = SwissMap::new_swiss();
+
+        for s in data.iter() {
+            *map.entry(*s).or_insert(0) += 1;
+        }
+        for s in data.iter().step_by(2) {
+            map.entry(*s).and_modify(|v| *v += 1).or_insert(0);
+        }
+
+        for (i, s) in data.iter().enumerate() {
+            assert_eq!(map.get(s), Some(&if i % 2 == 0 { 2 } else { 1 }));
+        }
+
+        for s in data.iter().step_by(3) {
+            match map.entry(*s) {
+                Entry::Occupied(entry) => assert_eq!(entry.remove_entry().0, *s),
+                Entry::Vacant(_) => panic!("{} should be in the map", s),
+            }
+        }
+
+        assert_eq!(map.len(), data.len() - data.len().div_ceil(3));
+        assert!(map.assert_count());
+
+        assert!(map.try_insert(data[0], 7).is_ok());
+        let err = map.try_insert(data[0], 8).unwrap_err();
+        assert_eq!((*err.entry.get(), err.value), (7, 8));
+        assert_eq!(map.get_key_value(&data[0]), Some((&data[0], &7)));
+    }
+
+    #[test]
+    fn follows_config() {
+        let config = FastMapConfig::new().max_load_factor(0.5).growth_factor(4).min_load_factor(0.1);
+        let mut map: SwissMap<u64, u64> = SwissMap::with_config_and_hasher(config, BuildMurmur2_64a::new());
+
+        for i in 0..1000 {
+            map.insert(i, i);
+            assert!((map.len() + map.tombstones()) * 2 <= map.capacity());
+        }
+        assert_eq!(map.capacity(), 4096);
+
+        for i in 0..990 {
+            map.remove(&i);
+        }
+        assert!(map.capacity() < 4096);
+        assert!(map.assert_count());
+
+        map.shrink_to_fit();
+        assert_eq!(map.capacity(), 32);
+
+        for i in 990..1000 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+
+        let config = FastMapConfig::new().check_policy(CheckPolicy::Amortized(64));
+        let mut map: SwissMap<u64, u64> = SwissMap::with_config_and_hasher(config, BuildMurmur2_64a::new());
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.len(), 10_000);
+        assert!(map.load_rate() <= 70.0);
+    }
+
+    #[test]
+    fn try_reserve_reports_overflow() {
+        let mut map: SwissMap<u64, u64> = SwissMap::new_swiss();
+        map.insert(21, 42);
+
+        assert_eq!(map.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(map.try_reserve(usize::MAX / 2), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(map.get(&21), Some(&42));
+
+        assert_eq!(map.try_reserve(1000), Ok(()));
+        let capacity = map.capacity();
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.capacity(), capacity);
+    }
+
+    #[test]
+    fn retain_and_drain() {
+        let data = get_random_range(1000);
+        let mut map: SwissMap<u64, u64> = data.iter().map(|&s| (s, s)).collect();
+
+        map.retain(|k, v| { *v = v.wrapping_add(1); k % 2 == 0 });
+        for s in data.iter() {
+            assert_eq!(map.get(s).cloned(), if s % 2 == 0 { Some(s.wrapping_add(1)) } else { None });
+        }
+        assert!(map.assert_count());
+
+        for v in map.values_mut() {
+            *v = 0;
+        }
+
+        let len = map.len();
+        let mut drained: Vec<(u64, u64)> = map.drain().collect();
+        drained.sort();
+        assert_eq!(drained.len(), len);
+        assert!(drained.iter().all(|&(k, v)| k % 2 == 0 && v == 0));
+
+        assert!(map.is_empty());
+        assert_eq!(map.tombstones(), 0);
+        assert!(map.assert_count());
+    }
+
+    #[test]
+    fn shares_the_fast_map_api() {
+        let data = get_random_range(1000);
+        let mut map: SwissMap<u64, u64> = SwissMap::new_swiss();
+
+        for s in data.iter() {
+            assert_eq!(map.insert_fallible(*s, *s), Ok(None));
+        }
+        assert_eq!(map.insert_fallible(data[0], 1), Ok(Some(data[0])));
+        map.insert(data[0], data[0]);
+
+        let forward: Vec<_> = map.iter().collect();
+        let mut backward: Vec<_> = map.iter().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(map.iter().len(), data.len());
+        assert_eq!(map.keys().rev().len(), data.len());
+
+        let mut odd: Vec<(u64, u64)> = map.extract_if(|k, _| k % 2 == 1).collect();
+        odd.sort();
+        assert!(odd.iter().all(|&(k, v)| k % 2 == 1 && k == v));
+        assert_eq!(map.len() + odd.len(), data.len());
+        assert!(map.iter().all(|(k, _)| k % 2 == 0));
+        assert!(map.assert_count());
+
+        let mut keys: Vec<u64> = map.clone().into_keys().collect();
+        let mut values: Vec<u64> = map.into_values().rev().collect();
+        keys.sort();
+        values.sort();
+        assert_eq!(keys, values);
+        assert_eq!(keys, data.iter().cloned().filter(|k| k % 2 == 0).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn owned_iterators_drop_the_rest() {
+        let counter = Rc::new(());
+        let mut map: SwissMap<u64, Rc<()>> = SwissMap::new_swiss();
+
+        for i in 0..100 {
+            map.insert(i, counter.clone());
+        }
+
+        let mut drain = map.drain();
+        assert_eq!(drain.len(), 100);
+        drain.next();
+        drop(drain);
+        assert_eq!(Rc::strong_count(&counter), 1);
+        assert!(map.is_empty());
+
+        for i in 0..100 {
+            map.insert(i, counter.clone());
+        }
+
+        let mut iter = map.clone().into_iter();
+        assert_eq!(Rc::strong_count(&counter), 201);
+        assert_eq!(iter.len(), 100);
+        iter.next();
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 101);
+
+        drop(map);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn std_traits() {
+        let data = get_random_range(1000);
+
+        let map: SwissMap<u64, u64> = data.iter().map(|&s| (s, s / 2)).collect();
+        assert_eq!(map.len(), data.len());
+        assert_eq!(map[&data[21]], data[21] / 2);
+
+        let copy = map.clone();
+        assert_eq!(copy, map);
+        assert!(copy.assert_count());
+
+        let mut other: SwissMap<u64, u64> = SwissMap::default();
+        other.extend(map.iter());
+        assert_eq!(other, map);
+        other.insert(data[0], 0);
+        assert_ne!(other, map);
+
+        let mut owned: Vec<(u64, u64)> = map.into_iter().collect();
+        owned.sort();
+        assert_eq!(owned, data.iter().map(|&s| (s, s / 2)).collect::<Vec<_>>());
+
+        let small = SwissMap::<u64, u64>::from([(21, 42)]);
+        assert_eq!(format!("{:?}", small), "{21: 42}");
+    }
+
+
+    fn get_random_range(count: usize) -> Vec<u64> {
+        use rand::{Rng, SeedableRng, StdRng};
+
+        let mut vec = Vec::new();
+
+        let seed: &[_] = &[4, 2, 4, 2];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        for _ in 0..count {
+            vec.push(rng.gen::<u64>());
+        }
+
+        vec.sort();
+        vec.dedup();
+
+        vec
+    }
+}